@@ -1,6 +1,7 @@
 use magnetite::{
+    background::backgrounds::{EnvironmentMap, PhysicalSky},
     image_encoder::{ImageCrateRGBu8Encoder, ImageEncoder},
-    random_world, render, Camera, Point3, RenderOptions, Vec3,
+    random_world, render, Camera, Point3, Real, RenderOptions, Vec3,
 };
 use std::{error::Error, path::PathBuf};
 
@@ -20,6 +21,19 @@ struct RandomBallsCli {
     /// Output file directory.
     #[arg(short, long)]
     output: Option<PathBuf>,
+
+    /// Render with wavelengths of light instead of RGB.
+    #[arg(long)]
+    spectral: bool,
+
+    /// Light the scene with an equirectangular environment map, like a Radiance `.hdr` or OpenEXR file, instead of
+    /// the sky.
+    #[arg(long)]
+    environment: Option<PathBuf>,
+
+    /// Light the scene with a physical sky, with the sun this many degrees above the horizon.
+    #[arg(long)]
+    sun: Option<Real>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -27,26 +41,34 @@ fn main() -> Result<(), Box<dyn Error>> {
         width,
         samples,
         output,
+        spectral,
+        environment,
+        sun,
     } = RandomBallsCli::parse();
 
     // Image output options:
-    const ASPECT_RATIO: f64 = 3.0 / 2.0;
+    const ASPECT_RATIO: Real = 3.0 / 2.0;
     // const width: u32 = 1200;
-    let image_height: u32 = (width as f64 / ASPECT_RATIO) as u32;
+    let image_height: u32 = (width as Real / ASPECT_RATIO) as u32;
     let filename = output.unwrap_or(PathBuf::from("/output.png"));
     // The image encoder to use. This can be any type that implements ImageEncoder.
     type ImageEncoder = ImageCrateRGBu8Encoder;
 
     // Camera options:
-    const VERTICAL_FIELD_OF_VIEW: f64 = 20.0;
-    const FOCUS_DISTANCE: f64 = 10.0;
-    const APERTURE: f64 = 0.1;
+    const VERTICAL_FIELD_OF_VIEW: Real = 20.0;
+    const FOCUS_DISTANCE: Real = 10.0;
+    const APERTURE: Real = 0.1;
 
     // Renderer options:
     const MAX_BOUNCES: u32 = 16;
 
     // Generate a scene to render.
     let world = random_world();
+    let background = match (environment, sun) {
+        (Some(path), _) => Some(EnvironmentMap::open(path)?.into()),
+        (None, Some(sun)) => Some(PhysicalSky::new(sun.to_radians(), 0.6, 3.0).into()),
+        (None, None) => None,
+    };
 
     let render_options: RenderOptions = RenderOptions {
         image_width: width as u32,
@@ -55,6 +77,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         max_bounces: MAX_BOUNCES,
         show_progress_bar: true,
         use_bvh: true,
+        use_bvh4: true,
+        bvh_cache: None,
+        spectral,
+        background,
+        lights: Vec::new(),
     };
 
     // Generate a camera at a cool angle.
@@ -68,9 +95,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         FOCUS_DISTANCE,
     );
 
-    render::<ImageEncoder>(render_options, camera, world)?
-        .save_image(filename)
-        .unwrap();
+    render::<ImageEncoder>(render_options, camera, world)?.save_image(filename)?;
 
     Ok(())
 }
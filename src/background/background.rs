@@ -0,0 +1,40 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{Colour, Real, Vec3};
+
+/// Trait for the light arriving from infinitely far away, which is what rays that don't hit anything see.
+///
+/// Backgrounds with bright spots, like the sun in an environment map, can also be sampled directly by the renderer
+/// the same way it samples lights, which is much less noisy than waiting for rays to find them.
+pub trait Background {
+    /// The light arriving from the background along `direction`, which points away from the scene and doesn't need to
+    /// be normalised.
+    fn colour(&self, direction: &Vec3<Real>) -> Colour;
+
+    /// Return true if the background can pick directions with [Background::sample_direction], so the renderer should
+    /// sample it as a light.
+    fn can_sample(&self) -> bool {
+        false
+    }
+
+    /// Pick a random direction towards the background, favouring the directions most light comes from.
+    fn sample_direction(&self) -> Option<Vec3<Real>> {
+        None
+    }
+
+    /// The probability density, with respect to solid angle, of [Background::sample_direction] picking `direction`.
+    fn direction_pdf(&self, _direction: &Vec3<Real>) -> Real {
+        0.0
+    }
+}
+
+/// A background trait object that can be shared between threads.
+pub type ArcBackground = Arc<dyn Background + Send + Sync>;
+
+// This lets render options that hold a background still derive `Debug`.
+impl fmt::Debug for dyn Background + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Background")
+    }
+}
@@ -0,0 +1,317 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use image::{DynamicImage, ImageError};
+use num_traits::FloatConst;
+use rand::Rng;
+
+use crate::{
+    background::{ArcBackground, Background},
+    texture::textures::ImageTexture,
+    Colour, Real, Vec3,
+};
+
+/// A piecewise constant probability distribution over `[0, 1)`, with one piece for each value it was built from.
+struct Distribution {
+    /// The running total of the values, so the last entry is the sum of all of them.
+    cdf: Vec<Real>,
+}
+
+impl Distribution {
+    fn new(values: impl IntoIterator<Item = Real>) -> Self {
+        let cdf = values
+            .into_iter()
+            .scan(0.0, |total, value| {
+                *total += value.max(0.0);
+                Some(*total)
+            })
+            .collect();
+
+        Self { cdf }
+    }
+
+    fn total(&self) -> Real {
+        self.cdf.last().copied().unwrap_or(0.0)
+    }
+
+    fn value(&self, i: usize) -> Real {
+        self.cdf[i] - if i > 0 { self.cdf[i - 1] } else { 0.0 }
+    }
+
+    /// Turn a uniform random number into the index of the piece it lands in and how far through the piece it is.
+    fn sample(&self, u: Real) -> (usize, Real) {
+        let target = u * self.total();
+        let i = self
+            .cdf
+            .partition_point(|&total| total <= target)
+            .min(self.cdf.len() - 1);
+        let value = self.value(i);
+        if value <= 0.0 {
+            return (i, 0.5);
+        }
+        let start = self.cdf[i] - value;
+
+        (i, ((target - start) / value).clamp(0.0, 1.0))
+    }
+
+    /// The density of [Distribution::sample] anywhere in piece `i`.
+    fn pdf(&self, i: usize) -> Real {
+        self.value(i) * self.cdf.len() as Real / self.total()
+    }
+}
+
+/// How close to the edge of a pixel sampled directions can be, as a fraction of the pixel.
+const EDGE: Real = 1e-3;
+
+/// A background lit by a panoramic image, usually a high dynamic range photo of a real place in a Radiance `.hdr` or
+/// OpenEXR file.
+///
+/// The image uses the equirectangular projection, where `u` goes around the horizon and `v` goes from straight down
+/// to straight up, and the centre of the image is towards `+x`. Directions are picked in proportion to how bright
+/// the image is, taking into account how it's stretched near the poles, so small bright lights like the sun can be
+/// sampled directly.
+pub struct EnvironmentMap {
+    image: ImageTexture,
+    /// How likely each row of the image is to be picked.
+    rows: Distribution,
+    /// How likely each pixel is to be picked once its row has been.
+    columns: Vec<Distribution>,
+    /// How far the image is turned around the vertical axis, in radians.
+    pub rotation: Real,
+    /// How much to scale the brightness of the image by.
+    pub intensity: Real,
+}
+
+impl EnvironmentMap {
+    /// Load an environment map from a file. See [ImageTexture::open] for how colours are read from different kinds of
+    /// image.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        Ok(Self::new(ImageTexture::open(path)?))
+    }
+
+    /// Create an environment map from an image that has already been loaded.
+    pub fn from_image(image: &DynamicImage) -> Self {
+        Self::new(ImageTexture::from_image(image))
+    }
+
+    /// Create an environment map from an image texture, building the distribution used to sample it.
+    /// ```
+    /// # use image::{DynamicImage, Rgb, Rgb32FImage};
+    /// # use magnetite::background::{backgrounds::EnvironmentMap, Background};
+    /// # use magnetite::texture::textures::ImageTexture;
+    /// // A dim image with one bright pixel, near the top and towards `+x`.
+    /// let mut image = Rgb32FImage::from_pixel(16, 8, Rgb([0.1, 0.1, 0.1]));
+    /// image.put_pixel(8, 2, Rgb([100.0, 100.0, 100.0]));
+    /// let map = EnvironmentMap::new(ImageTexture::from_image(&DynamicImage::ImageRgb32F(image)));
+    ///
+    /// // Most directions are picked towards the bright pixel.
+    /// let towards_light = (0..1000)
+    ///     .filter_map(|_| map.sample_direction())
+    ///     .filter(|direction| map.colour(direction).x() > 1.0)
+    ///     .count();
+    /// assert!(towards_light > 500);
+    /// ```
+    pub fn new(image: ImageTexture) -> Self {
+        let (width, height) = image.size();
+
+        // Rows near the poles cover less of the sphere, so they're less likely to be picked.
+        let columns: Vec<Distribution> = (0..height)
+            .map(|y| {
+                let v = (y as Real + 0.5) / height as Real;
+                let sin_theta = (v * Real::PI()).sin();
+
+                Distribution::new((0..width).map(|x| {
+                    let u = (x as Real + 0.5) / width as Real;
+                    Self::lookup(&image, u, v).luminance() * sin_theta
+                }))
+            })
+            .collect();
+        let rows = Distribution::new(columns.iter().map(Distribution::total));
+
+        Self {
+            image,
+            rows,
+            columns,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    /// Look up the colour of the image at `(u, v)`. The image wraps around the horizon, but the top and bottom rows
+    /// are the poles, so `v` is kept to the middle of them rather than wrapping onto the other pole.
+    fn lookup(image: &ImageTexture, u: Real, v: Real) -> Colour {
+        let half_row = 0.5 / image.size().1 as Real;
+
+        image.sample(u, v.clamp(half_row, 1.0 - half_row), 0.0)
+    }
+
+    /// Turn a direction in world space around the vertical axis by `angle`.
+    fn rotate(direction: &Vec3<Real>, angle: Real) -> Vec3<Real> {
+        let (sin, cos) = angle.sin_cos();
+
+        Vec3::new(
+            direction.x() * cos + direction.z() * sin,
+            direction.y(),
+            direction.z() * cos - direction.x() * sin,
+        )
+    }
+
+    /// The image coordinates a direction points at.
+    fn uv(&self, direction: &Vec3<Real>) -> (Real, Real) {
+        let direction = Self::rotate(&direction.unit_vector(), -self.rotation);
+        // This stays accurate near the poles, where `acos` of the height would round to exactly 0 or π.
+        let theta = direction.x().hypot(direction.z()).atan2(-direction.y());
+        let phi = (-direction.z()).atan2(direction.x()) + Real::PI();
+
+        (phi / (2.0 * Real::PI()), theta / Real::PI())
+    }
+
+    /// The density of picking image coordinates `(u, v)`, with respect to area in the image.
+    fn uv_pdf(&self, u: Real, v: Real) -> Real {
+        let (width, height) = self.image.size();
+        let x = ((u * width as Real) as usize).min(width - 1);
+        let y = ((v * height as Real) as usize).min(height - 1);
+
+        let row_pdf = self.rows.pdf(y);
+        if row_pdf <= 0.0 {
+            return 0.0;
+        }
+
+        row_pdf * self.columns[y].pdf(x)
+    }
+}
+
+impl Into<ArcBackground> for EnvironmentMap {
+    fn into(self) -> ArcBackground {
+        Arc::new(self)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn colour(&self, direction: &Vec3<Real>) -> Colour {
+        let (u, v) = self.uv(direction);
+
+        Self::lookup(&self.image, u, v) * self.intensity
+    }
+
+    fn can_sample(&self) -> bool {
+        self.rows.total() > 0.0
+    }
+
+    fn sample_direction(&self) -> Option<Vec3<Real>> {
+        if !self.can_sample() {
+            return None;
+        }
+
+        let (width, height) = self.image.size();
+        let mut rng = rand::thread_rng();
+        let (y, dy) = self.rows.sample(rng.gen_range(0.0..1.0));
+        let (x, dx) = self.columns[y].sample(rng.gen_range(0.0..1.0));
+
+        // Keep away from the edges of the pixel, so rounding when the direction is turned back into image coordinates
+        // can't land it in a neighbouring pixel that's never picked.
+        let (dx, dy) = (dx.clamp(EDGE, 1.0 - EDGE), dy.clamp(EDGE, 1.0 - EDGE));
+
+        let u = (x as Real + dx) / width as Real;
+        let v = (y as Real + dy) / height as Real;
+        let (theta, phi) = (v * Real::PI(), u * 2.0 * Real::PI());
+        let sin_theta = theta.sin();
+
+        let direction = Vec3::new(-sin_theta * phi.cos(), -theta.cos(), sin_theta * phi.sin());
+        Some(Self::rotate(&direction, self.rotation))
+    }
+
+    fn direction_pdf(&self, direction: &Vec3<Real>) -> Real {
+        if !self.can_sample() {
+            return 0.0;
+        }
+
+        let (u, v) = self.uv(direction);
+        let sin_theta = (v * Real::PI()).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // The image is wrapped around the sphere, which stretches each pixel over a solid angle of
+        // `2π² sin θ / (width × height)`.
+        self.uv_pdf(u, v) / (2.0 * Real::PI() * Real::PI() * sin_theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, Rgb32FImage};
+
+    use super::*;
+
+    #[test]
+    fn sampling_matches_pdf() {
+        const SAMPLES: usize = 200_000;
+
+        let image = Rgb32FImage::from_fn(32, 16, |x, y| {
+            let brightness = if (x, y) == (5, 3) {
+                500.0
+            } else {
+                (x + y) as f32 / 10.0
+            };
+            Rgb([brightness, brightness, brightness])
+        });
+        let mut map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image));
+        map.rotation = 0.7;
+
+        // The density integrates to one over the sphere of directions.
+        let total = (0..SAMPLES)
+            .map(|_| map.direction_pdf(&Vec3::random_unit_vector()))
+            .sum::<Real>()
+            * 4.0
+            * Real::PI()
+            / SAMPLES as Real;
+        assert!((total - 1.0).abs() < 0.05, "{total}");
+
+        // Estimating the light from the whole background by sampling it gives the same answer as sampling uniformly.
+        let sampled = (0..SAMPLES)
+            .filter_map(|_| map.sample_direction())
+            .map(|direction| map.colour(&direction).x() / map.direction_pdf(&direction))
+            .sum::<Real>()
+            / SAMPLES as Real;
+        let uniform = (0..SAMPLES)
+            .map(|_| map.colour(&Vec3::random_unit_vector()).x())
+            .sum::<Real>()
+            * 4.0
+            * Real::PI()
+            / SAMPLES as Real;
+        assert!(
+            (sampled - uniform).abs() < 0.05 * uniform,
+            "{sampled} {uniform}"
+        );
+    }
+
+    #[test]
+    fn poles_dont_bleed_into_each_other() {
+        // A bright sky above a black ground.
+        let image = Rgb32FImage::from_fn(8, 4, |_, y| {
+            let brightness = if y < 2 { 1.0 } else { 0.0 };
+            Rgb([brightness, brightness, brightness])
+        });
+        let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image));
+
+        assert_eq!(map.colour(&Vec3::new(0.0, 1.0, 0.0)).x(), 1.0);
+        assert_eq!(map.colour(&Vec3::new(0.0, -1.0, 0.0)).x(), 0.0);
+        assert_eq!(map.direction_pdf(&Vec3::new(0.3, -1.0, 0.0)), 0.0);
+        for _ in 0..1000 {
+            assert!(map.sample_direction().unwrap().y() > 0.0);
+        }
+    }
+
+    #[test]
+    fn zero_weight_pieces_dont_give_nan() {
+        let distribution = Distribution::new([0.0, 2.0, 0.0, 1.0, 0.0]);
+
+        // Zero weight pieces are skipped, except when rounding pushes the random number all the way to the end.
+        for u in [0.0, 0.3, 2.0 / 3.0, 0.9, 1.0] {
+            let (i, fraction) = distribution.sample(u);
+            assert!((0.0..=1.0).contains(&fraction), "{u} {fraction}");
+            assert!(distribution.value(i) > 0.0 || u >= 1.0, "{u} {i}");
+        }
+    }
+}
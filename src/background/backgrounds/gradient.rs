@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use crate::{
+    background::{ArcBackground, Background},
+    Colour, Real, Vec3,
+};
+
+/// A background that blends from one colour straight down to another straight up.
+pub struct Gradient {
+    pub bottom: Colour,
+    pub top: Colour,
+}
+
+impl Gradient {
+    pub fn new(bottom: Colour, top: Colour) -> Self {
+        Self { bottom, top }
+    }
+
+    /// A pale blue sky, which is the background scenes are rendered with by default.
+    pub fn sky() -> Self {
+        Self::new(Colour::new(0.9, 0.9, 0.9), Colour::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Into<ArcBackground> for Gradient {
+    fn into(self) -> ArcBackground {
+        Arc::new(self)
+    }
+}
+
+impl Background for Gradient {
+    fn colour(&self, direction: &Vec3<Real>) -> Colour {
+        let t = 0.5 * (direction.unit_vector().y() + 1.0);
+
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
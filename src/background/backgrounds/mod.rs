@@ -0,0 +1,7 @@
+mod environment_map;
+mod gradient;
+mod physical_sky;
+
+pub use environment_map::EnvironmentMap;
+pub use gradient::Gradient;
+pub use physical_sky::PhysicalSky;
@@ -0,0 +1,381 @@
+use std::sync::Arc;
+
+use num_traits::FloatConst;
+use rand::Rng;
+
+use crate::{
+    background::{ArcBackground, Background},
+    spectrum::{cie_xyz, xyz_to_rgb, MAX_WAVELENGTH, MIN_WAVELENGTH},
+    Colour, Real, Vec3,
+};
+
+/// The angular radius of the sun seen from the ground, in radians.
+const SUN_ANGULAR_RADIUS: Real = 0.004654;
+
+/// The temperature of the blackbody the sun's light is modelled on, in kelvin.
+const SUN_TEMPERATURE: Real = 5778.0;
+
+/// How many lumens a watt of light at each wavelength is worth, for the colour matching functions.
+const LUMINOUS_EFFICACY: Real = 683.0;
+
+/// The Perez sky model, which gives how bright the sky is in a direction relative to the zenith. `theta` is the angle
+/// of the direction from the zenith and `gamma` is its angle from the sun.
+#[derive(Clone, Copy, Debug)]
+struct Perez([Real; 5]);
+
+impl Perez {
+    /// The coefficients for a turbidity, where `coefficients` holds the slope and intercept of each of them.
+    fn new(turbidity: Real, coefficients: [(Real, Real); 5]) -> Self {
+        Self(coefficients.map(|(slope, intercept)| slope * turbidity + intercept))
+    }
+
+    fn value(&self, cos_theta: Real, gamma: Real) -> Real {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+
+        (1.0 + a * (b / cos_theta.max(1e-4)).exp())
+            * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// The spectral radiance of a blackbody, in watts per square metre per steradian per metre, at a wavelength in
+/// nanometres.
+// The constants are the exact SI values, which are more precise than `f32` can hold.
+#[allow(clippy::excessive_precision)]
+fn blackbody(wavelength: Real, temperature: Real) -> Real {
+    const H: Real = 6.62607015e-34;
+    const C: Real = 2.99792458e8;
+    const K: Real = 1.380649e-23;
+
+    let wavelength = wavelength * 1e-9;
+    2.0 * H * C * C / (wavelength.powi(5) * ((H * C / (wavelength * K * temperature)).exp() - 1.0))
+}
+
+/// A daylight sky with the sun in it, using the analytic model from "A Practical Analytic Model for Daylight"
+/// (Preetham, Shirley & Smits 1999).
+///
+/// The sky's colour depends on where the sun is and on the turbidity, which is how hazy the air is. 2 is a very clear
+/// day, and 10 is thick haze. The sun is a disc with its real size in the sky, so it can be sampled directly like any
+/// other light, and its colour comes from the light of a blackbody filtered by the air it shines through. Below the
+/// horizon, the sky is black.
+///
+/// Brightness is in kilocandelas per square metre, scaled by `intensity`.
+/// ```
+/// # use magnetite::background::{backgrounds::PhysicalSky, Background};
+/// # use magnetite::Vec3;
+/// let sky = PhysicalSky::new(0.5, 0.0, 3.0);
+///
+/// // The sky around the sun is brighter than the sky opposite it.
+/// let towards_sun = sky.colour(&Vec3::new(1.0, 0.3, 0.2));
+/// let away_from_sun = sky.colour(&Vec3::new(-1.0, 0.3, -0.2));
+/// assert!(towards_sun.luminance() > away_from_sun.luminance());
+///
+/// // Skies are blue.
+/// let zenith = sky.colour(&Vec3::new(0.0, 1.0, 0.0));
+/// assert!(zenith.z() > zenith.x());
+/// ```
+pub struct PhysicalSky {
+    sun_direction: Vec3<Real>,
+    /// The angle of the sun from the zenith.
+    sun_theta: Real,
+    /// The brightness and chromaticity of the sky at the zenith, as CIE xyY.
+    zenith: Vec3<Real>,
+    perez: [Perez; 3],
+    sun_colour: Colour,
+    /// How often the sun is sampled rather than the rest of the sky.
+    sun_probability: Real,
+    /// How much to scale the brightness of the sky and sun by. The default brings a white surface in the midday sun
+    /// to about 1.
+    pub intensity: Real,
+}
+
+impl PhysicalSky {
+    /// Create a sky with the sun `sun_elevation` radians above the horizon. The sun's azimuth goes around the
+    /// horizon, where 0 puts it towards `+x` and π/2 towards `-z`.
+    pub fn new(sun_elevation: Real, sun_azimuth: Real, turbidity: Real) -> Self {
+        let turbidity = turbidity.clamp(1.7, 10.0);
+        let sun_theta = Real::FRAC_PI_2() - sun_elevation.clamp(0.0, Real::FRAC_PI_2());
+        let sun_direction = Vec3::new(
+            sun_elevation.cos() * sun_azimuth.cos(),
+            sun_elevation.sin(),
+            -sun_elevation.cos() * sun_azimuth.sin(),
+        );
+
+        let perez = [
+            Perez::new(
+                turbidity,
+                [
+                    (0.1787, -1.4630),
+                    (-0.3554, 0.4275),
+                    (-0.0227, 5.3251),
+                    (0.1206, -2.5771),
+                    (-0.0670, 0.3703),
+                ],
+            ),
+            Perez::new(
+                turbidity,
+                [
+                    (-0.0193, -0.2592),
+                    (-0.0665, 0.0008),
+                    (-0.0004, 0.2125),
+                    (-0.0641, -0.8989),
+                    (-0.0033, 0.0452),
+                ],
+            ),
+            Perez::new(
+                turbidity,
+                [
+                    (-0.0167, -0.2608),
+                    (-0.0950, 0.0092),
+                    (-0.0079, 0.2102),
+                    (-0.0441, -1.6537),
+                    (-0.0109, 0.0529),
+                ],
+            ),
+        ];
+
+        let chi = (4.0 / 9.0 - turbidity / 120.0) * (Real::PI() - 2.0 * sun_theta);
+        let luminance = (4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192;
+        let chromaticity = |m: [[Real; 4]; 3]| {
+            let t = [turbidity * turbidity, turbidity, 1.0];
+            let s = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+
+            (0..3)
+                .map(|i| t[i] * (0..4).map(|j| m[i][j] * s[j]).sum::<Real>())
+                .sum::<Real>()
+        };
+        let zenith = Vec3::new(
+            chromaticity([
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ]),
+            chromaticity([
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ]),
+            luminance.max(0.0),
+        );
+
+        let mut sky = Self {
+            sun_direction,
+            sun_theta,
+            zenith,
+            perez,
+            sun_colour: Colour::new(0.0, 0.0, 0.0),
+            sun_probability: 0.0,
+            intensity: 0.025,
+        };
+        sky.sun_colour = sky.sun_radiance(turbidity);
+        sky.sun_probability = sky.sun_share();
+
+        sky
+    }
+
+    /// The direction towards the middle of the sun.
+    pub fn sun_direction(&self) -> Vec3<Real> {
+        self.sun_direction
+    }
+
+    /// The cosine of the angle the sun covers around its middle.
+    fn sun_cos_radius() -> Real {
+        SUN_ANGULAR_RADIUS.cos()
+    }
+
+    /// The solid angle the sun covers.
+    fn sun_solid_angle() -> Real {
+        2.0 * Real::PI() * (1.0 - Self::sun_cos_radius())
+    }
+
+    /// The brightness of the sun's disc, from the light of a blackbody that's been scattered out of the way by air
+    /// molecules and haze on its way down.
+    fn sun_radiance(&self, turbidity: Real) -> Colour {
+        if self.sun_direction.y() <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        // How much air the light goes through compared to coming straight down, and how hazy that air is.
+        let sun_degrees = self.sun_theta.to_degrees();
+        let air_mass = 1.0 / (self.sun_theta.cos() + 0.15 * (93.885 - sun_degrees).powf(-1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+
+        const STEPS: usize = 80;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / STEPS as Real;
+        let xyz = (0..STEPS)
+            .map(|i| {
+                let wavelength = MIN_WAVELENGTH + (i as Real + 0.5) * step;
+                let micrometres = wavelength / 1000.0;
+                let rayleigh = (-0.008735 * micrometres.powf(-4.08) * air_mass).exp();
+                let aerosol = (-beta * micrometres.powf(-1.3) * air_mass).exp();
+
+                cie_xyz(wavelength)
+                    * (blackbody(wavelength, SUN_TEMPERATURE) * rayleigh * aerosol * step * 1e-9)
+            })
+            .fold(Vec3::default(), |sum, xyz| sum + xyz);
+
+        // Convert from candelas to kilocandelas to match the sky.
+        xyz_to_rgb(&(xyz * LUMINOUS_EFFICACY / 1000.0))
+    }
+
+    /// The sky's colour in a direction, without the sun.
+    fn sky_colour(&self, direction: &Vec3<Real>) -> Colour {
+        let direction = direction.unit_vector();
+        let cos_theta = direction.y();
+        if cos_theta <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [y_luminance, x, y] = [0, 1, 2].map(|i| {
+            let zenith = [self.zenith.z(), self.zenith.x(), self.zenith.y()][i];
+            let perez = self.perez[i];
+
+            zenith * perez.value(cos_theta, gamma) / perez.value(1.0, self.sun_theta)
+        });
+
+        if y <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        let xyz = Vec3::new(
+            x * y_luminance / y,
+            y_luminance,
+            (1.0 - x - y) * y_luminance / y,
+        );
+        let Vec3(r, g, b) = xyz_to_rgb(&xyz);
+
+        Colour::new(r.max(0.0), g.max(0.0), b.max(0.0))
+    }
+
+    /// The fraction of the light falling on the ground that comes straight from the sun, which is how often it's
+    /// sampled. This is kept away from 0 and 1 so neither the sun nor the sky is left to chance.
+    fn sun_share(&self) -> Real {
+        if self.sun_direction.y() <= 0.0 {
+            return 0.0;
+        }
+
+        let sun = self.sun_colour.luminance() * Self::sun_solid_angle();
+
+        // Average the sky over the upper hemisphere on a grid.
+        const STEPS: usize = 32;
+        let sky = (0..STEPS * STEPS)
+            .map(|i| {
+                let cos_theta = ((i / STEPS) as Real + 0.5) / STEPS as Real;
+                let phi = ((i % STEPS) as Real + 0.5) / STEPS as Real * 2.0 * Real::PI();
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let direction = Vec3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+
+                self.sky_colour(&direction).luminance()
+            })
+            .sum::<Real>()
+            * 2.0
+            * Real::PI()
+            / (STEPS * STEPS) as Real;
+
+        if sun + sky <= 0.0 {
+            return 0.0;
+        }
+
+        (sun / (sun + sky)).clamp(0.1, 0.9)
+    }
+
+    fn in_sun(&self, direction: &Vec3<Real>) -> bool {
+        direction.unit_vector().dot(&self.sun_direction) >= Self::sun_cos_radius()
+    }
+}
+
+impl Into<ArcBackground> for PhysicalSky {
+    fn into(self) -> ArcBackground {
+        Arc::new(self)
+    }
+}
+
+impl Background for PhysicalSky {
+    fn colour(&self, direction: &Vec3<Real>) -> Colour {
+        let mut colour = self.sky_colour(direction);
+        if self.in_sun(direction) && direction.y() > 0.0 {
+            colour += self.sun_colour;
+        }
+
+        colour * self.intensity
+    }
+
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    /// Pick a direction towards the sun, or uniformly from the upper hemisphere for the rest of the sky.
+    fn sample_direction(&self) -> Option<Vec3<Real>> {
+        let mut rng = rand::thread_rng();
+        let phi = rng.gen_range(0.0..2.0 * Real::PI());
+
+        if rng.gen_range(0.0..1.0) < self.sun_probability {
+            let cos_theta = 1.0 - rng.gen_range(0.0..1.0) * (1.0 - Self::sun_cos_radius());
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let (tangent, bitangent) = self.sun_direction.orthonormal_basis();
+
+            Some(
+                tangent * (sin_theta * phi.cos())
+                    + bitangent * (sin_theta * phi.sin())
+                    + self.sun_direction * cos_theta,
+            )
+        } else {
+            // Flip the range so the direction is never exactly on the horizon, where the density is zero. This happens
+            // often enough to notice in single precision.
+            let cos_theta = 1.0 - rng.gen_range(0.0..1.0 as Real);
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+            Some(Vec3::new(
+                sin_theta * phi.cos(),
+                cos_theta,
+                sin_theta * phi.sin(),
+            ))
+        }
+    }
+
+    fn direction_pdf(&self, direction: &Vec3<Real>) -> Real {
+        let mut pdf = 0.0;
+
+        if self.in_sun(direction) {
+            pdf += self.sun_probability / Self::sun_solid_angle();
+        }
+        if direction.y() > 0.0 {
+            pdf += (1.0 - self.sun_probability) / (2.0 * Real::PI());
+        }
+
+        pdf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling_matches_pdf() {
+        const SAMPLES: usize = 200_000;
+        let sky = PhysicalSky::new(0.6, 1.0, 4.0);
+
+        // The density integrates to one, which needs the sun's part to be integrated separately since it's so small.
+        let sky_part = (0..SAMPLES)
+            .map(|_| Vec3::random_unit_vector())
+            .filter(|direction| !sky.in_sun(direction))
+            .map(|direction| sky.direction_pdf(&direction))
+            .sum::<Real>()
+            * 4.0
+            * Real::PI()
+            / SAMPLES as Real;
+        let sun_part = sky.sun_probability;
+        assert!(
+            (sky_part + sun_part - 1.0).abs() < 0.02,
+            "{sky_part} {sun_part}"
+        );
+
+        // Sampled directions are always somewhere the density is positive.
+        for _ in 0..SAMPLES {
+            let direction = sky.sample_direction().unwrap();
+            assert!(sky.direction_pdf(&direction) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod background;
+pub mod backgrounds;
+
+pub use background::{ArcBackground, Background};
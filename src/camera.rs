@@ -1,26 +1,26 @@
-use crate::{point3::Point3, ray::Ray, vec3::Vec3};
+use crate::{point3::Point3, ray::Ray, vec3::Vec3, Real};
 
 #[derive(Debug, Clone)]
 pub struct Camera {
     pub origin: Point3,
-    pub horizontal: Vec3<f64>,
-    pub vertical: Vec3<f64>,
-    pub lower_left_corner: Vec3<f64>,
+    pub horizontal: Vec3<Real>,
+    pub vertical: Vec3<Real>,
+    pub lower_left_corner: Vec3<Real>,
 
-    u: Vec3<f64>,
-    v: Vec3<f64>,
-    lens_radius: f64,
+    u: Vec3<Real>,
+    v: Vec3<Real>,
+    lens_radius: Real,
 }
 
 impl Camera {
     pub fn new(
         look_from: Point3,
         look_at: Point3,
-        vup: Vec3<f64>,
-        vfov: f64,
-        aspect_ratio: f64,
-        aperture: f64,
-        focus_dist: f64,
+        vup: Vec3<Real>,
+        vfov: Real,
+        aspect_ratio: Real,
+        aperture: Real,
+        focus_dist: Real,
     ) -> Self {
         let theta = vfov.to_radians();
         let h = (theta / 2.0).tan();
@@ -51,7 +51,7 @@ impl Camera {
 }
 
 impl Camera {
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
+    pub fn get_ray(&self, s: Real, t: Real) -> Ray {
         let rd = Vec3::random_in_unit_disk() * self.lens_radius;
         let offset = self.u * rd.x() + self.v * rd.y();
 
@@ -59,6 +59,29 @@ impl Camera {
         let direction = self.lower_left_corner + (self.horizontal * s) + (self.vertical * t)
             - self.origin
             - offset;
-        Ray { origin, direction }
+        Ray::new(origin, direction)
+    }
+
+    /// The angle between the rays through neighbouring pixels in the middle of an image `image_height` pixels tall.
+    /// ```
+    /// # use magnetite::{Camera, Point3, Real, Vec3};
+    /// // With a 90° field of view, the image is twice as tall as it is far away, and that's split over 900 pixels.
+    /// let camera = Camera::new(
+    ///     Point3::new(0.0, 0.0, 0.0),
+    ///     Point3::new(0.0, 0.0, -1.0),
+    ///     Vec3::new(0.0, 1.0, 0.0),
+    ///     90.0,
+    ///     1.0,
+    ///     0.0,
+    ///     3.0,
+    /// );
+    /// assert!((camera.pixel_spread(900) - 2.0 / 900.0 as Real).abs() < 1e-6);
+    /// ```
+    pub fn pixel_spread(&self, image_height: u32) -> Real {
+        let focus_distance = (self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0
+            - self.origin)
+            .length();
+
+        self.vertical.length() / focus_distance / image_height.max(1) as Real
     }
 }
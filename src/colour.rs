@@ -1,8 +1,8 @@
 use rand::{rngs::ThreadRng, Rng};
 
-use crate::vec3::Vec3;
+use crate::{vec3::Vec3, Real};
 
-pub type Colour = Vec3<f64>;
+pub type Colour = Vec3<Real>;
 
 impl Into<[u8; 3]> for Colour {
     fn into(self) -> [u8; 3] {
@@ -23,4 +23,13 @@ impl Colour {
 
         Self(r, g, b)
     }
+
+    /// The brightness of a linear colour as perceived by people, using the Rec. 709 coefficients.
+    /// ```
+    /// # use magnetite::Colour;
+    /// assert!((Colour::new(1.0, 1.0, 1.0).luminance() - 1.0).abs() < 1e-6);
+    /// ```
+    pub fn luminance(&self) -> Real {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
 }
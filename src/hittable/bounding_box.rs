@@ -1,4 +1,4 @@
-use crate::{ray::Ray, Point3};
+use crate::{ray::Ray, Point3, Real};
 
 use super::Interval;
 
@@ -13,8 +13,8 @@ pub struct BoundingBox {
 impl BoundingBox {
     /// Create a new bounding box that contains two points.
     /// ```
-    /// # use raytracing_in_a_weekend::hittable::{BoundingBox, Interval};
-    /// # use raytracing_in_a_weekend::Point3;
+    /// # use magnetite::hittable::{BoundingBox, Interval};
+    /// # use magnetite::Point3;
     /// let p1 = Point3::new(1.0, 3.0, 2.0);
     /// let p2 = Point3::new(0.0, 5.0, 1.0);
     ///
@@ -33,8 +33,8 @@ impl BoundingBox {
 
     /// Create an empty bounding box.
     /// ```
-    /// # use raytracing_in_a_weekend::hittable::{BoundingBox, Interval};
-    /// # use raytracing_in_a_weekend::Point3;
+    /// # use magnetite::hittable::{BoundingBox, Interval};
+    /// # use magnetite::Point3;
     /// let bb = BoundingBox::empty();
     /// assert_eq!(bb.x, Interval::new(0.0, 0.0));
     /// assert_eq!(bb.y, Interval::new(0.0, 0.0));
@@ -60,7 +60,7 @@ impl BoundingBox {
         }
 
         // We don't want to modify the original ray_t in case there isn't a hit.
-        let mut ray_collision_t = ray_t.clone();
+        let mut ray_collision_t = *ray_t;
 
         let axes = [
             (&self.x, ray.origin.x(), ray.direction.x()),
@@ -92,7 +92,7 @@ impl BoundingBox {
     }
 
     /// Get the 3D volume of the bounding box.
-    pub fn volume(&self) -> f64 {
+    pub fn volume(&self) -> Real {
         self.x.size() * self.y.size() * self.z.size()
     }
 }
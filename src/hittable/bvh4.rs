@@ -0,0 +1,579 @@
+use std::sync::Arc;
+
+use crate::{ray::Ray, Real};
+
+use super::{
+    ArcHittable, BoundingBox, BvhChild, BvhLayout, BvhLayoutNode, HitRecord, Hittable, Interval,
+};
+
+/// The far end of each slab is scaled by this so that rounding errors from using the inverse of the ray's direction
+/// can never make a box that should be hit get missed. This is `1 + 2 * gamma(3)` from PBRT, section 3.9.2.
+const ROBUST_SCALE: Real =
+    1.0 + 2.0 * (3.0 * Real::EPSILON * 0.5) / (1.0 - 3.0 * Real::EPSILON * 0.5);
+
+/// A ray prepared for testing against many bounding boxes.
+struct RayData {
+    origin: [Real; 3],
+    inverse_direction: [Real; 3],
+}
+
+impl RayData {
+    fn new(ray: &Ray) -> Self {
+        Self {
+            origin: [ray.origin.x(), ray.origin.y(), ray.origin.z()],
+            inverse_direction: [
+                1.0 / ray.direction.x(),
+                1.0 / ray.direction.y(),
+                1.0 / ray.direction.z(),
+            ],
+        }
+    }
+}
+
+/// The result of testing a ray against four bounding boxes at once.
+#[derive(Debug, PartialEq)]
+struct Hits4 {
+    /// Bit `i` is set if box `i` was hit.
+    mask: u8,
+    /// The `t` at which the ray enters each box.
+    near: [Real; 4],
+}
+
+/// `min` and `max` with the same semantics as the SSE and AVX instructions, so the scalar and SIMD paths give bit-for-bit
+/// identical results. If either value is NaN, the second one is returned.
+fn simd_min(a: Real, b: Real) -> Real {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+fn simd_max(a: Real, b: Real) -> Real {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+/// Four axis-aligned bounding boxes stored as a structure of arrays, so all four can be tested at once.
+///
+/// Unused slots are marked invalid in the node's mask rather than given special bounds.
+#[derive(Clone, Copy, Debug)]
+#[repr(C, align(32))]
+struct Aabb4 {
+    /// The minimum of each box along each axis, indexed as `min[axis][box]`.
+    min: [[Real; 4]; 3],
+    /// The maximum of each box along each axis, indexed as `max[axis][box]`.
+    max: [[Real; 4]; 3],
+}
+
+impl Aabb4 {
+    fn new(boxes: &[BoundingBox]) -> Self {
+        let mut aabb = Self {
+            min: [[0.0; 4]; 3],
+            max: [[0.0; 4]; 3],
+        };
+
+        for (i, bb) in boxes.iter().enumerate() {
+            for (axis, interval) in [bb.x, bb.y, bb.z].into_iter().enumerate() {
+                aabb.min[axis][i] = interval.min;
+                aabb.max[axis][i] = interval.max;
+            }
+        }
+
+        aabb
+    }
+
+    /// Test a ray against all four boxes, using SIMD instructions if the CPU supports them.
+    #[inline]
+    fn hit(&self, ray: &RayData, ray_t: &Interval, use_simd: bool) -> Hits4 {
+        #[cfg(target_arch = "x86_64")]
+        if use_simd {
+            // Safety: `use_simd` is only true if the CPU was detected to support the instructions.
+            return unsafe { self.hit_simd(ray, ray_t) };
+        }
+
+        let _ = use_simd;
+        self.hit_scalar(ray, ray_t)
+    }
+
+    /// The slab test for four boxes, one at a time.
+    fn hit_scalar(&self, ray: &RayData, ray_t: &Interval) -> Hits4 {
+        let mut near = [ray_t.min; 4];
+        let mut far = [ray_t.max; 4];
+
+        for axis in 0..3 {
+            for i in 0..4 {
+                let t0 = (self.min[axis][i] - ray.origin[axis]) * ray.inverse_direction[axis];
+                let t1 = (self.max[axis][i] - ray.origin[axis]) * ray.inverse_direction[axis];
+
+                // If a slab's `t` is NaN (the ray starts on the slab and runs parallel to it), the max and min below
+                // ignore it rather than rejecting the box.
+                near[i] = simd_max(simd_min(t0, t1), near[i]);
+                far[i] = simd_min(simd_max(t0, t1) * ROBUST_SCALE, far[i]);
+            }
+        }
+
+        let mut mask = 0;
+        for i in 0..4 {
+            if near[i] <= far[i] {
+                mask |= 1 << i;
+            }
+        }
+
+        Hits4 { mask, near }
+    }
+
+    /// The slab test for four boxes at once using AVX. This must give exactly the same results as [Aabb4::hit_scalar].
+    #[cfg(all(target_arch = "x86_64", not(feature = "f32")))]
+    #[target_feature(enable = "avx")]
+    unsafe fn hit_simd(&self, ray: &RayData, ray_t: &Interval) -> Hits4 {
+        use std::arch::x86_64::*;
+
+        let mut near = _mm256_set1_pd(ray_t.min);
+        let mut far = _mm256_set1_pd(ray_t.max);
+        let robust_scale = _mm256_set1_pd(ROBUST_SCALE);
+
+        for axis in 0..3 {
+            let origin = _mm256_set1_pd(ray.origin[axis]);
+            let inverse_direction = _mm256_set1_pd(ray.inverse_direction[axis]);
+
+            // Aabb4 is aligned to 32 bytes and each row is 32 bytes long, so these loads are aligned.
+            let min = _mm256_load_pd(self.min[axis].as_ptr());
+            let max = _mm256_load_pd(self.max[axis].as_ptr());
+
+            let t0 = _mm256_mul_pd(_mm256_sub_pd(min, origin), inverse_direction);
+            let t1 = _mm256_mul_pd(_mm256_sub_pd(max, origin), inverse_direction);
+
+            near = _mm256_max_pd(_mm256_min_pd(t0, t1), near);
+            far = _mm256_min_pd(_mm256_mul_pd(_mm256_max_pd(t0, t1), robust_scale), far);
+        }
+
+        let mask = _mm256_movemask_pd(_mm256_cmp_pd::<_CMP_LE_OQ>(near, far)) as u8;
+
+        let mut near_array = [0.0; 4];
+        _mm256_storeu_pd(near_array.as_mut_ptr(), near);
+
+        Hits4 {
+            mask,
+            near: near_array,
+        }
+    }
+
+    /// The slab test for four boxes at once using SSE. This must give exactly the same results as [Aabb4::hit_scalar].
+    #[cfg(all(target_arch = "x86_64", feature = "f32"))]
+    #[target_feature(enable = "sse")]
+    unsafe fn hit_simd(&self, ray: &RayData, ray_t: &Interval) -> Hits4 {
+        use std::arch::x86_64::*;
+
+        let mut near = _mm_set1_ps(ray_t.min);
+        let mut far = _mm_set1_ps(ray_t.max);
+        let robust_scale = _mm_set1_ps(ROBUST_SCALE);
+
+        for axis in 0..3 {
+            let origin = _mm_set1_ps(ray.origin[axis]);
+            let inverse_direction = _mm_set1_ps(ray.inverse_direction[axis]);
+
+            // Aabb4 is aligned to 32 bytes and each row is 16 bytes long, so these loads are aligned.
+            let min = _mm_load_ps(self.min[axis].as_ptr());
+            let max = _mm_load_ps(self.max[axis].as_ptr());
+
+            let t0 = _mm_mul_ps(_mm_sub_ps(min, origin), inverse_direction);
+            let t1 = _mm_mul_ps(_mm_sub_ps(max, origin), inverse_direction);
+
+            near = _mm_max_ps(_mm_min_ps(t0, t1), near);
+            far = _mm_min_ps(_mm_mul_ps(_mm_max_ps(t0, t1), robust_scale), far);
+        }
+
+        let mask = _mm_movemask_ps(_mm_cmple_ps(near, far)) as u8;
+
+        let mut near_array = [0.0; 4];
+        _mm_storeu_ps(near_array.as_mut_ptr(), near);
+
+        Hits4 {
+            mask,
+            near: near_array,
+        }
+    }
+
+    /// Return true if the CPU supports the instructions used by [Aabb4::hit_simd].
+    fn simd_supported() -> bool {
+        #[cfg(all(target_arch = "x86_64", not(feature = "f32")))]
+        return is_x86_feature_detected!("avx");
+
+        #[cfg(all(target_arch = "x86_64", feature = "f32"))]
+        return is_x86_feature_detected!("sse");
+
+        #[cfg(not(target_arch = "x86_64"))]
+        return false;
+    }
+}
+
+/// A child of a [Bvh4Node].
+#[derive(Clone, Copy, Debug)]
+enum Bvh4Child {
+    /// An index into the tree's objects.
+    Primitive(u32),
+    /// An index into the tree's nodes.
+    Node(u32),
+}
+
+/// A node of a [Bvh4] tree with up to four children.
+struct Bvh4Node {
+    bounds: Aabb4,
+    children: [Bvh4Child; 4],
+    /// Bit `i` is set if `children[i]` is in use.
+    valid: u8,
+}
+
+/// A Bounding Volume Hierarchy where each node has up to four children.
+///
+/// This is built by collapsing a binary [BvhLayout], so the tree is just as good as a [BvhNode](super::BvhNode) tree
+/// but only half as deep. All four child bounding boxes of a node are stored next to each other, and are tested
+/// against a ray at the same time using AVX (or SSE with the `f32` feature) if the CPU supports it, falling back to
+/// scalar code if not. The ray's inverse direction is calculated once per traversal instead of dividing for every box.
+///
+/// Box tests are conservative, so a box is never missed because of rounding errors. Because every object that could
+/// be the closest hit is still tested with its own [Hittable::hit], the closest hit is exactly the same as the one a
+/// [BvhNode](super::BvhNode) tree finds.
+pub struct Bvh4 {
+    objects: Vec<ArcHittable>,
+    nodes: Vec<Bvh4Node>,
+    bounding_box: BoundingBox,
+    use_simd: bool,
+}
+
+impl Into<ArcHittable> for Bvh4 {
+    fn into(self) -> ArcHittable {
+        Arc::new(self)
+    }
+}
+
+impl Bvh4 {
+    /// Construct a 4-wide BVH tree from a collection of [ArcHittable] objects.
+    pub fn create(objects: impl Into<Vec<ArcHittable>>) -> Self {
+        let objects: Vec<ArcHittable> = objects.into();
+        let boxes: Vec<BoundingBox> = objects.iter().map(|o| o.bounding_box()).collect();
+        let layout = BvhLayout::build(&boxes);
+
+        Self::from_layout(objects, &layout)
+    }
+
+    /// Construct a 4-wide BVH tree by collapsing the layout of a binary tree, where the layout's primitives are
+    /// indices into `objects`.
+    ///
+    /// Panics if the layout wasn't built from the same number of objects.
+    pub fn from_layout(objects: impl Into<Vec<ArcHittable>>, layout: &BvhLayout) -> Self {
+        let objects: Vec<ArcHittable> = objects.into();
+        assert_eq!(
+            objects.len(),
+            layout.primitive_count(),
+            "BVH layout was built from a different number of objects."
+        );
+
+        let use_simd = Aabb4::simd_supported();
+
+        let mut builder = Builder {
+            layout: layout.nodes(),
+            object_boxes: objects.iter().map(|o| o.bounding_box()).collect(),
+            node_boxes: Vec::with_capacity(layout.nodes().len()),
+            nodes: Vec::with_capacity(layout.nodes().len() / 2 + 1),
+        };
+
+        // Children always come before their parents, so every node's box can be found in one pass.
+        for node in layout.nodes() {
+            let bb = BoundingBox::containing(
+                &builder.child_box(node.left),
+                &builder.child_box(node.right),
+            );
+            builder.node_boxes.push(bb);
+        }
+
+        let root = layout.nodes().len() as u32 - 1;
+        builder.build_node(root);
+
+        Self {
+            objects,
+            nodes: builder.nodes,
+            bounding_box: builder.node_boxes[root as usize],
+            use_simd,
+        }
+    }
+
+    /// Find the closest hit below a node, narrowing `closest` each time a closer hit is found.
+    fn hit_node<'a>(
+        &'a self,
+        node: &Bvh4Node,
+        ray: &Ray,
+        ray_data: &RayData,
+        ray_t: &Interval,
+        closest: &mut Option<HitRecord<'a>>,
+    ) {
+        let max = closest.as_ref().map_or(ray_t.max, |record| record.t);
+        let hits = node.bounds.hit(
+            ray_data,
+            &Interval {
+                min: ray_t.min,
+                max,
+            },
+            self.use_simd,
+        );
+        let mask = hits.mask & node.valid;
+
+        // Visit the children in the order the ray enters them, so later children can be skipped once a hit is found.
+        let mut order = [(0usize, 0.0); 4];
+        let mut count = 0;
+        for i in 0..4 {
+            if mask & (1 << i) != 0 {
+                let mut j = count;
+                while j > 0 && order[j - 1].1 > hits.near[i] {
+                    order[j] = order[j - 1];
+                    j -= 1;
+                }
+                order[j] = (i, hits.near[i]);
+                count += 1;
+            }
+        }
+
+        for &(i, near) in &order[..count] {
+            let max = closest.as_ref().map_or(ray_t.max, |record| record.t);
+            if near > max * ROBUST_SCALE {
+                continue;
+            }
+
+            match node.children[i] {
+                Bvh4Child::Primitive(p) => {
+                    if let Some(record) = self.objects[p as usize].hit(
+                        ray,
+                        &Interval {
+                            min: ray_t.min,
+                            max,
+                        },
+                    ) {
+                        *closest = Some(record);
+                    }
+                }
+                Bvh4Child::Node(n) => {
+                    self.hit_node(&self.nodes[n as usize], ray, ray_data, ray_t, closest)
+                }
+            }
+        }
+    }
+
+    /// Return true if anything below a node is hit.
+    fn occluded_node(
+        &self,
+        node: &Bvh4Node,
+        ray: &Ray,
+        ray_data: &RayData,
+        ray_t: &Interval,
+    ) -> bool {
+        let mask = node.bounds.hit(ray_data, ray_t, self.use_simd).mask & node.valid;
+
+        (0..4)
+            .filter(|i| mask & (1 << i) != 0)
+            .any(|i| match node.children[i] {
+                Bvh4Child::Primitive(p) => self.objects[p as usize].occluded(ray, ray_t),
+                Bvh4Child::Node(n) => {
+                    self.occluded_node(&self.nodes[n as usize], ray, ray_data, ray_t)
+                }
+            })
+    }
+}
+
+impl Hittable for Bvh4 {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
+        let ray_data = RayData::new(ray);
+        let mut closest = None;
+
+        // The root is always the last node.
+        let root = self.nodes.last()?;
+        self.hit_node(root, ray, &ray_data, ray_t, &mut closest);
+
+        closest
+    }
+
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        let ray_data = RayData::new(ray);
+
+        match self.nodes.last() {
+            Some(root) => self.occluded_node(root, ray, &ray_data, ray_t),
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounding_box
+    }
+}
+
+/// Temporary state used while collapsing a binary layout into a 4-wide tree.
+struct Builder<'a> {
+    layout: &'a [BvhLayoutNode],
+    object_boxes: Vec<BoundingBox>,
+    node_boxes: Vec<BoundingBox>,
+    nodes: Vec<Bvh4Node>,
+}
+
+impl Builder<'_> {
+    fn child_box(&self, child: BvhChild) -> BoundingBox {
+        match child {
+            BvhChild::Empty => BoundingBox::empty(),
+            BvhChild::Primitive(i) => self.object_boxes[i as usize],
+            BvhChild::Node(i) => self.node_boxes[i as usize],
+        }
+    }
+
+    /// Half the surface area of a bounding box, used to decide which child is most worth splitting.
+    fn half_area(bb: &BoundingBox) -> Real {
+        let (x, y, z) = (bb.x.size(), bb.y.size(), bb.z.size());
+        x * y + y * z + z * x
+    }
+
+    /// Collapse a binary layout node (and up to two levels of its descendants) into a 4-wide node, returning the new
+    /// node's index.
+    fn build_node(&mut self, layout_node: u32) -> u32 {
+        let node = self.layout[layout_node as usize];
+        let mut children: Vec<BvhChild> = [node.left, node.right]
+            .into_iter()
+            .filter(|c| *c != BvhChild::Empty)
+            .collect();
+
+        // Keep replacing the biggest node child with its own children until there are four.
+        while children.len() < 4 {
+            let biggest = children
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| match c {
+                    BvhChild::Node(n) => Some((i, Self::half_area(&self.node_boxes[*n as usize]))),
+                    _ => None,
+                })
+                .reduce(|a, b| if a.1 >= b.1 { a } else { b });
+
+            let Some((i, _)) = biggest else {
+                break;
+            };
+
+            let BvhChild::Node(n) = children[i] else {
+                unreachable!()
+            };
+            let grandchildren = self.layout[n as usize];
+
+            // Don't split a node into more than four children.
+            let new_children: Vec<BvhChild> = [grandchildren.left, grandchildren.right]
+                .into_iter()
+                .filter(|c| *c != BvhChild::Empty)
+                .collect();
+            if children.len() - 1 + new_children.len() > 4 {
+                break;
+            }
+
+            children.splice(i..=i, new_children);
+        }
+
+        let boxes: Vec<BoundingBox> = children.iter().map(|c| self.child_box(*c)).collect();
+        let mut bvh4_children = [Bvh4Child::Primitive(0); 4];
+        let mut valid = 0;
+
+        for (i, child) in children.iter().enumerate() {
+            bvh4_children[i] = match *child {
+                BvhChild::Primitive(p) => Bvh4Child::Primitive(p),
+                BvhChild::Node(n) => Bvh4Child::Node(self.build_node(n)),
+                BvhChild::Empty => unreachable!(),
+            };
+            valid |= 1 << i;
+        }
+
+        self.nodes.push(Bvh4Node {
+            bounds: Aabb4::new(&boxes),
+            children: bvh4_children,
+            valid,
+        });
+
+        self.nodes.len() as u32 - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{hittable::BvhNode, random_world, Point3, Vec3};
+
+    fn random_point(rng: &mut StdRng, range: Real) -> Point3 {
+        Point3::new(
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+        )
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn simd_box_test_matches_scalar() {
+        if !Aabb4::simd_supported() {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(29);
+
+        for _ in 0..10_000 {
+            let boxes: Vec<BoundingBox> = (0..4)
+                .map(|_| BoundingBox::new(random_point(&mut rng, 5.0), random_point(&mut rng, 5.0)))
+                .collect();
+            let bounds = Aabb4::new(&boxes);
+
+            // Include some axis-aligned directions to exercise infinite and NaN slabs.
+            let mut direction = random_point(&mut rng, 1.0);
+            if rng.gen_bool(0.2) {
+                direction.0 = 0.0;
+            }
+            let ray = Ray::new(random_point(&mut rng, 10.0), direction);
+            let ray_data = RayData::new(&ray);
+            let ray_t = Interval::new(0.001, Real::INFINITY);
+
+            let scalar = bounds.hit_scalar(&ray_data, &ray_t);
+            let simd = unsafe { bounds.hit_simd(&ray_data, &ray_t) };
+
+            assert_eq!(scalar.mask, simd.mask);
+            for i in 0..4 {
+                assert_eq!(scalar.near[i].to_bits(), simd.near[i].to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn bvh4_hits_match_bvh() {
+        let world = random_world();
+        let bvh = BvhNode::create(world.clone());
+        let bvh4 = Bvh4::create(world);
+
+        let mut rng = StdRng::seed_from_u64(29);
+        let ray_t = Interval::new(0.001, Real::INFINITY);
+
+        for _ in 0..10_000 {
+            let origin = random_point(&mut rng, 15.0) + Vec3::new(0.0, 15.0, 0.0);
+            let target = random_point(&mut rng, 12.0);
+            let ray = Ray::new(origin, target - origin);
+
+            match (bvh.hit(&ray, &ray_t), bvh4.hit(&ray, &ray_t)) {
+                (None, None) => (),
+                (Some(a), Some(b)) => {
+                    assert_eq!(a.t.to_bits(), b.t.to_bits());
+                    assert_eq!(a.hit_location, b.hit_location);
+                    assert_eq!(a.normal, b.normal);
+                }
+                (a, b) => panic!(
+                    "BVH hit {:?} but BVH4 hit {:?}",
+                    a.map(|r| r.t),
+                    b.map(|r| r.t)
+                ),
+            }
+
+            assert_eq!(bvh.occluded(&ray, &ray_t), bvh4.occluded(&ray, &ray_t));
+        }
+    }
+}
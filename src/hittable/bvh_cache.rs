@@ -0,0 +1,348 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::{BoundingBox, BvhChild, BvhLayout, BvhLayoutNode};
+
+/// The bytes every BVH cache file starts with.
+const MAGIC: &[u8; 8] = b"MGNTBVH\0";
+
+/// The version of the cache file format. Bump this whenever the format or the BVH construction algorithm changes so
+/// that old caches are rebuilt rather than misread.
+const VERSION: u32 = 1;
+
+/// A 64-bit FNV-1a hasher.
+///
+/// [std::collections::hash_map::DefaultHasher] isn't guaranteed to give the same output between Rust releases, which
+/// would make every cache stale after a compiler update.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A writer that hashes everything that passes through it, so a checksum can be appended to the end of the file.
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Fnv1a,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.write(bytes);
+        self.inner.write_all(bytes)
+    }
+}
+
+/// A reader that hashes everything that passes through it, so the checksum at the end of the file can be verified.
+struct HashingReader<R: Read> {
+    inner: R,
+    hasher: Fnv1a,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn read_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.inner.read_exact(&mut bytes)?;
+        self.hasher.write(&bytes);
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+}
+
+fn write_child<W: Write>(writer: &mut HashingWriter<W>, child: BvhChild) -> io::Result<()> {
+    let (tag, index) = match child {
+        BvhChild::Empty => (0u8, 0u32),
+        BvhChild::Primitive(i) => (1, i),
+        BvhChild::Node(i) => (2, i),
+    };
+
+    writer.write_all(&[tag])?;
+    writer.write_all(&index.to_le_bytes())
+}
+
+fn read_child<R: Read>(reader: &mut HashingReader<R>) -> io::Result<BvhChild> {
+    let [tag] = reader.read_array()?;
+    let index = reader.read_u32()?;
+
+    match tag {
+        0 => Ok(BvhChild::Empty),
+        1 => Ok(BvhChild::Primitive(index)),
+        2 => Ok(BvhChild::Node(index)),
+        _ => Err(invalid_data("Unknown BVH child type.")),
+    }
+}
+
+impl BvhLayout {
+    /// Hash the contents of a scene as far as the BVH is concerned.
+    ///
+    /// The shape of the tree only depends on the bounding boxes of the objects (and their order), so those are all
+    /// that gets hashed.
+    pub fn scene_hash(boxes: &[BoundingBox]) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(&(boxes.len() as u64).to_le_bytes());
+
+        for bb in boxes {
+            for interval in [bb.x, bb.y, bb.z] {
+                hasher.write(&interval.min.to_le_bytes());
+                hasher.write(&interval.max.to_le_bytes());
+            }
+        }
+
+        hasher.0
+    }
+
+    /// Serialise the layout to a versioned binary format, tagged with the hash of the scene it was built from.
+    ///
+    /// The format is little-endian and consists of a magic number, the format version, the scene hash, the number of
+    /// primitives and nodes, the nodes themselves, and finally a checksum of everything before it.
+    pub fn write_to<W: Write>(&self, writer: W, scene_hash: u64) -> io::Result<()> {
+        let mut writer = HashingWriter {
+            inner: writer,
+            hasher: Fnv1a::new(),
+        };
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&scene_hash.to_le_bytes())?;
+        writer.write_all(&(self.primitive_count as u64).to_le_bytes())?;
+        writer.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+
+        for node in &self.nodes {
+            write_child(&mut writer, node.left)?;
+            write_child(&mut writer, node.right)?;
+        }
+
+        let checksum = writer.hasher.0;
+        writer.inner.write_all(&checksum.to_le_bytes())?;
+        writer.inner.flush()
+    }
+
+    /// Deserialise a layout written by [BvhLayout::write_to] for a scene with `primitive_count` objects.
+    ///
+    /// Returns an [io::ErrorKind::InvalidData] error if the data is from a different version, was built from a scene
+    /// with a different hash or number of objects, or is corrupt in any way.
+    /// ```
+    /// # use magnetite::hittable::{BoundingBox, BvhLayout};
+    /// # use magnetite::Point3;
+    /// let boxes = [
+    ///     BoundingBox::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+    ///     BoundingBox::new(Point3::new(2.0, 0.0, 0.0), Point3::new(3.0, 1.0, 1.0)),
+    /// ];
+    /// let layout = BvhLayout::build(&boxes);
+    ///
+    /// let mut bytes = Vec::new();
+    /// layout.write_to(&mut bytes, 42).unwrap();
+    ///
+    /// assert_eq!(BvhLayout::read_from(bytes.as_slice(), 42, 2).unwrap(), layout);
+    /// // A cache for a different scene is rejected.
+    /// assert!(BvhLayout::read_from(bytes.as_slice(), 43, 2).is_err());
+    /// assert!(BvhLayout::read_from(bytes.as_slice(), 42, 3).is_err());
+    /// // So is a corrupt one.
+    /// bytes[37] ^= 0xff;
+    /// assert!(BvhLayout::read_from(bytes.as_slice(), 42, 2).is_err());
+    /// ```
+    pub fn read_from<R: Read>(
+        reader: R,
+        scene_hash: u64,
+        primitive_count: usize,
+    ) -> io::Result<Self> {
+        let mut reader = HashingReader {
+            inner: reader,
+            hasher: Fnv1a::new(),
+        };
+
+        if &reader.read_array::<8>()? != MAGIC {
+            return Err(invalid_data("Not a BVH cache file."));
+        }
+
+        if reader.read_u32()? != VERSION {
+            return Err(invalid_data("Unsupported BVH cache version."));
+        }
+
+        if reader.read_u64()? != scene_hash {
+            return Err(invalid_data("BVH cache was built from a different scene."));
+        }
+
+        // The counts haven't been verified by the checksum yet, so nothing can be allocated from them until they're
+        // known to match the scene.
+        if reader.read_u64()? != primitive_count as u64 {
+            return Err(invalid_data(
+                "BVH cache was built from a different number of objects.",
+            ));
+        }
+
+        let node_count = reader.read_u64()? as usize;
+
+        // A binary tree never has more nodes than primitives (plus the root), so anything bigger must be corrupt.
+        if node_count > primitive_count.max(1) {
+            return Err(invalid_data("BVH cache has too many nodes."));
+        }
+
+        let mut nodes = Vec::new();
+        for _ in 0..node_count {
+            let left = read_child(&mut reader)?;
+            let right = read_child(&mut reader)?;
+            nodes.push(BvhLayoutNode { left, right });
+        }
+
+        let expected_checksum = reader.hasher.0;
+        let mut checksum = [0; 8];
+        reader.inner.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != expected_checksum {
+            return Err(invalid_data("BVH cache checksum doesn't match."));
+        }
+
+        let layout = Self {
+            nodes,
+            primitive_count,
+        };
+
+        if layout.is_valid() {
+            Ok(layout)
+        } else {
+            Err(invalid_data("BVH cache doesn't describe a valid tree."))
+        }
+    }
+
+    /// Load the layout for a set of bounding boxes from a cache file, or build it if the cache is missing, stale or
+    /// corrupt.
+    ///
+    /// When the layout has to be built, the cache file is (re)written. The cache is only ever an optimisation, so
+    /// failing to write it is not treated as an error.
+    pub fn load_or_build<P: AsRef<Path>>(path: P, boxes: &[BoundingBox]) -> Self {
+        let path = path.as_ref();
+        let hash = Self::scene_hash(boxes);
+
+        let cached = File::open(path)
+            .and_then(|file| Self::read_from(BufReader::new(file), hash, boxes.len()));
+        if let Ok(layout) = cached {
+            return layout;
+        }
+
+        let layout = Self::build(boxes);
+        let _ = layout.save(path, hash);
+        layout
+    }
+
+    /// Write the layout to a file. The data is written to a temporary file first and then moved into place, so a
+    /// crash part way through never leaves a truncated cache behind.
+    fn save(&self, path: &Path, scene_hash: u64) -> io::Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let result = File::create(&temp_path)
+            .and_then(|file| self.write_to(BufWriter::new(file), scene_hash))
+            .and_then(|_| fs::rename(&temp_path, path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{Point3, Real};
+
+    fn boxes(count: usize, spacing: Real) -> Vec<BoundingBox> {
+        (0..count)
+            .map(|i| {
+                let x = i as Real * spacing;
+                BoundingBox::new(Point3::new(x, 0.0, 0.0), Point3::new(x + 1.0, 1.0, 1.0))
+            })
+            .collect()
+    }
+
+    /// A cache file path that's unique to the test, so tests running at the same time don't share a cache.
+    fn cache_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("magnetite-{}-{name}.bvh", std::process::id()))
+    }
+
+    #[test]
+    fn stale_cache_is_rebuilt() {
+        let path = cache_path("stale");
+        let old_boxes = boxes(16, 2.0);
+        BvhLayout::load_or_build(&path, &old_boxes);
+
+        // The scene changed shape, so its hash doesn't match the cache any more.
+        let new_boxes = boxes(16, 3.0);
+        let layout = BvhLayout::load_or_build(&path, &new_boxes);
+        assert_eq!(layout, BvhLayout::build(&new_boxes));
+
+        // The cache was rewritten for the new scene.
+        let file = File::open(&path).unwrap();
+        let cached = BvhLayout::read_from(
+            BufReader::new(file),
+            BvhLayout::scene_hash(&new_boxes),
+            new_boxes.len(),
+        );
+        assert_eq!(cached.unwrap(), layout);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_cache_is_rebuilt() {
+        let path = cache_path("corrupt");
+        let boxes = boxes(16, 2.0);
+        let layout = BvhLayout::load_or_build(&path, &boxes);
+
+        // Cut the end off the file, like a write that was interrupted.
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 5]).unwrap();
+        let hash = BvhLayout::scene_hash(&boxes);
+        assert!(BvhLayout::read_from(File::open(&path).unwrap(), hash, boxes.len()).is_err());
+
+        assert_eq!(BvhLayout::load_or_build(&path, &boxes), layout);
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn huge_primitive_count_is_rejected() {
+        let boxes = boxes(4, 2.0);
+        let hash = BvhLayout::scene_hash(&boxes);
+        let layout = BvhLayout::build(&boxes);
+
+        // Claim the scene has an absurd number of objects, straight after the magic number, version and hash.
+        let mut bytes = Vec::new();
+        layout.write_to(&mut bytes, hash).unwrap();
+        bytes[20..28].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(BvhLayout::read_from(bytes.as_slice(), hash, boxes.len()).is_err());
+
+        // Layouts that claim too many objects are never valid, and are rejected before anything is allocated for them.
+        let layout = BvhLayout {
+            primitive_count: usize::MAX,
+            ..layout
+        };
+        assert!(!layout.is_valid());
+    }
+}
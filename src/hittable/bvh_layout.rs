@@ -0,0 +1,161 @@
+use itertools::Itertools;
+
+use super::BoundingBox;
+
+/// One of the two children of a node in a [BvhLayout].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BvhChild {
+    /// An empty child that can never be hit.
+    Empty,
+    /// An index into the list of objects the layout was built from.
+    Primitive(u32),
+    /// An index into the layout's list of nodes.
+    Node(u32),
+}
+
+/// A node of a [BvhLayout], pointing at its two children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BvhLayoutNode {
+    pub left: BvhChild,
+    pub right: BvhChild,
+}
+
+/// The shape of a BVH tree, described using indices instead of the objects themselves.
+///
+/// Building the tree is by far the slowest part of creating a [BvhNode](super::BvhNode), but the shape of the tree
+/// only depends on the bounding boxes of the objects. Separating the shape from the objects means it can be saved to
+/// disk and reused as long as the bounding boxes don't change.
+///
+/// Nodes are stored in the order they were created, so a node's children always come before it and the root node is
+/// always the last one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BvhLayout {
+    pub(super) nodes: Vec<BvhLayoutNode>,
+    pub(super) primitive_count: usize,
+}
+
+impl BvhLayout {
+    /// Build the layout of a BVH tree over a list of bounding boxes.
+    /// ```
+    /// # use magnetite::hittable::{BoundingBox, BvhChild, BvhLayout};
+    /// # use magnetite::Point3;
+    /// let boxes = [
+    ///     BoundingBox::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+    ///     BoundingBox::new(Point3::new(9.0, 9.0, 9.0), Point3::new(10.0, 10.0, 10.0)),
+    ///     BoundingBox::new(Point3::new(1.0, 1.0, 1.0), Point3::new(2.0, 2.0, 2.0)),
+    /// ];
+    ///
+    /// let layout = BvhLayout::build(&boxes);
+    /// assert_eq!(layout.nodes().len(), 3);
+    /// // The two boxes next to each other get paired up first.
+    /// assert_eq!(layout.nodes()[0].left, BvhChild::Primitive(0));
+    /// assert_eq!(layout.nodes()[0].right, BvhChild::Primitive(2));
+    /// ```
+    pub fn build(boxes: &[BoundingBox]) -> Self {
+        let mut nodes = Vec::with_capacity(boxes.len().max(1));
+
+        // The children that haven't been given a parent yet, along with their bounding boxes.
+        let mut orphans: Vec<(BvhChild, BoundingBox)> = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, bb)| (BvhChild::Primitive(i as u32), *bb))
+            .collect();
+
+        // Reduce the orphans into nodes until there are two or less left.
+        while orphans.len() >= 2 {
+            // Find the pair of orphans with the smallest combined bounding box.
+            let ((a, b), _) = (0..orphans.len())
+                .tuple_combinations()
+                .map(|(a, b)| {
+                    let bb = BoundingBox::containing(&orphans[a].1, &orphans[b].1);
+                    ((a, b), bb.volume())
+                })
+                .reduce(|x, y| if x.1 < y.1 { x } else { y })
+                .unwrap();
+
+            // `b` is always greater than `a`, so remove it first to keep `a`'s index valid.
+            let orphan_b = orphans.remove(b);
+            let orphan_a = orphans.remove(a);
+
+            let (node, bounding_box) = Self::node(orphan_a, orphan_b);
+            nodes.push(node);
+            orphans.push((BvhChild::Node(nodes.len() as u32 - 1), bounding_box));
+        }
+
+        // If there are two or less orphans left, create the root node from them.
+        let empty = (BvhChild::Empty, BoundingBox::empty());
+        let (root, _) = match orphans.len() {
+            0 => Self::node(empty, empty),
+            1 => Self::node(empty, orphans[0]),
+            2 => Self::node(orphans[0], orphans[1]),
+            _ => unreachable!(),
+        };
+        nodes.push(root);
+
+        Self {
+            nodes,
+            primitive_count: boxes.len(),
+        }
+    }
+
+    /// Create a node from two children, ensuring that the left's volume <= right's volume.
+    fn node(
+        (a, a_bb): (BvhChild, BoundingBox),
+        (b, b_bb): (BvhChild, BoundingBox),
+    ) -> (BvhLayoutNode, BoundingBox) {
+        let bounding_box = BoundingBox::containing(&a_bb, &b_bb);
+
+        let (left, right) = if a_bb.volume() <= b_bb.volume() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        (BvhLayoutNode { left, right }, bounding_box)
+    }
+
+    /// The nodes of the tree, in the order they were created. The last node is the root.
+    pub fn nodes(&self) -> &[BvhLayoutNode] {
+        &self.nodes
+    }
+
+    /// The number of objects the layout was built from.
+    pub fn primitive_count(&self) -> usize {
+        self.primitive_count
+    }
+
+    /// Check that the layout describes a valid tree, where every primitive and every node (apart from the root) has
+    /// exactly one parent, and every node only points at nodes that were created before it.
+    ///
+    /// This is used to reject layouts that have been loaded from a corrupt cache.
+    pub fn is_valid(&self) -> bool {
+        // Each node has room for two primitives, so a bigger count can't be right. Checking this first means a bogus
+        // count can't make the checks below allocate a huge amount of memory.
+        if self.nodes.is_empty() || self.primitive_count > 2 * self.nodes.len() {
+            return false;
+        }
+
+        let mut primitive_parents = vec![0u32; self.primitive_count];
+        let mut node_parents = vec![0u32; self.nodes.len()];
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            for child in [node.left, node.right] {
+                match child {
+                    BvhChild::Empty => (),
+                    BvhChild::Primitive(p) => match primitive_parents.get_mut(p as usize) {
+                        Some(parents) => *parents += 1,
+                        None => return false,
+                    },
+                    BvhChild::Node(n) if (n as usize) < i => node_parents[n as usize] += 1,
+                    BvhChild::Node(_) => return false,
+                }
+            }
+        }
+
+        let (root_parents, child_parents) = node_parents.split_last().unwrap();
+
+        *root_parents == 0
+            && child_parents.iter().all(|&p| p == 1)
+            && primitive_parents.iter().all(|&p| p == 1)
+    }
+}
@@ -1,10 +1,12 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::ray::Ray;
 
-use itertools::Itertools;
-
-use super::{hittables::NotHittable, ArcHittable, BoundingBox, HitRecord, Hittable, Interval};
+use super::{
+    hittables::NotHittable, ArcHittable, BoundingBox, BvhChild, BvhLayout, HitRecord, Hittable,
+    Interval,
+};
 
 /// A node of a Binary Volume Hierarchy tree.
 ///
@@ -12,7 +14,8 @@ use super::{hittables::NotHittable, ArcHittable, BoundingBox, HitRecord, Hittabl
 /// In the best case scenario, a BVH tree means a ray hit can be calculated in O(1) time, as long as the ray doesn't
 /// actually hit anything. With actual use on my machine, I have seen speedups of over 10x using BVH trees. The
 /// downside is that to calculate the best possible arrangement for the tree, the bounding box for every possible pair
-/// of objects in the scene must be calculated at least once.
+/// of objects in the scene must be calculated at least once. To avoid paying that cost on every run, the shape of the
+/// tree can be cached on disk using [BvhNode::create_cached].
 pub struct BvhNode {
     left: ArcHittable,
     right: ArcHittable,
@@ -28,38 +31,62 @@ impl Into<ArcHittable> for BvhNode {
 impl BvhNode {
     /// Construct a BVH tree from a collection of [ArcHittable] objects.
     pub fn create(objects: impl Into<Vec<ArcHittable>>) -> BvhNode {
-        // Create a mutable copy of the objects vec to work with.
-        let mut objects: Vec<ArcHittable> = objects.into();
-
-        // Reduce the objects in the vec into nodes until there are two or less left.
-        while objects.len() >= 2 {
-            // Find the pair of objects in the scene with the smallest bounding box and create a node from them.
-            let (node, _) = objects
-                .clone()
-                .into_iter()
-                .tuple_combinations()
-                .map(|(a, b)| {
-                    let node = BvhNode::new(a, b);
-                    let volume = node.bounding_box.volume();
-                    (node, volume)
-                })
-                .reduce(|a, b| if a.1 < b.1 { a } else { b })
-                .unwrap();
-
-            // Remove the new node's children from objects and push the new node into objects.
-            objects.retain(|x| !(Arc::ptr_eq(x, &node.left) || Arc::ptr_eq(x, &node.right)));
-            objects.push(node.into());
-        }
+        let objects: Vec<ArcHittable> = objects.into();
+        let layout = BvhLayout::build(&Self::bounding_boxes(&objects));
+
+        BvhNode::from_layout(&objects, &layout)
+    }
+
+    /// Construct a BVH tree from a collection of [ArcHittable] objects, reusing the tree's layout from a cache file if
+    /// it was built from the same objects. If the cache is missing, stale or corrupt, the tree is built from scratch
+    /// and the cache file is updated.
+    pub fn create_cached(
+        objects: impl Into<Vec<ArcHittable>>,
+        cache_path: impl AsRef<Path>,
+    ) -> BvhNode {
+        let objects: Vec<ArcHittable> = objects.into();
+        let layout = BvhLayout::load_or_build(cache_path, &Self::bounding_boxes(&objects));
+
+        BvhNode::from_layout(&objects, &layout)
+    }
 
-        // If there are two or less objects left, create a node from them.
-        let (left, right) = match objects.len() {
-            0 => (NotHittable.into(), NotHittable.into()),
-            1 => (NotHittable.into(), Arc::clone(&objects[0])),
-            2 => (Arc::clone(&objects[0]), Arc::clone(&objects[1])),
-            _ => unreachable!(),
+    /// Construct a BVH tree with a given layout, where the layout's primitives are indices into `objects`.
+    ///
+    /// Panics if the layout wasn't built from the same number of objects.
+    pub fn from_layout(objects: &[ArcHittable], layout: &BvhLayout) -> BvhNode {
+        assert_eq!(
+            objects.len(),
+            layout.primitive_count(),
+            "BVH layout was built from a different number of objects."
+        );
+
+        let (root, children) = layout
+            .nodes()
+            .split_last()
+            .expect("BVH layout should have a root node.");
+
+        // Children always come before their parents, so each node can be built from nodes that already exist.
+        let mut nodes: Vec<ArcHittable> = Vec::with_capacity(children.len());
+        let resolve = |child: BvhChild, nodes: &[ArcHittable]| -> ArcHittable {
+            match child {
+                BvhChild::Empty => NotHittable.into(),
+                BvhChild::Primitive(i) => Arc::clone(&objects[i as usize]),
+                BvhChild::Node(i) => Arc::clone(&nodes[i as usize]),
+            }
         };
 
-        BvhNode::new(left, right)
+        for node in children {
+            let left = resolve(node.left, &nodes);
+            let right = resolve(node.right, &nodes);
+            nodes.push(BvhNode::new(left, right).into());
+        }
+
+        BvhNode::new(resolve(root.left, &nodes), resolve(root.right, &nodes))
+    }
+
+    /// Get the bounding box of every object in a list.
+    fn bounding_boxes(objects: &[ArcHittable]) -> Vec<BoundingBox> {
+        objects.iter().map(|object| object.bounding_box()).collect()
     }
 
     /// Construct a new BVH node, ensuring that the left's volume <= right's volume.
@@ -83,7 +110,7 @@ impl BvhNode {
 }
 
 impl Hittable for BvhNode {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
         // Return early if the node's bounding box isn't hit.
         self.bounding_box.hit(ray, ray_t)?;
 
@@ -103,7 +130,49 @@ impl Hittable for BvhNode {
         }
     }
 
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Return early if the node's bounding box isn't hit.
+        if self.bounding_box.hit(ray, ray_t).is_none() {
+            return false;
+        }
+
+        // Only check the right child if the left one isn't hit.
+        self.left.occluded(ray, ray_t) || self.right.occluded(ray, ray_t)
+    }
+
     fn bounding_box(&self) -> BoundingBox {
         self.bounding_box
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        hittable::{hittable::tests::check_occluded_matches_hit, hittables::Sphere},
+        material::{materials::Lambertian, ArcMaterial},
+        Colour, Point3,
+    };
+
+    #[test]
+    fn occluded_matches_hit() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let mut rng = StdRng::seed_from_u64(27);
+
+        // Big spheres packed close together, so the children of most nodes overlap and a ray can hit both.
+        let spheres: Vec<ArcHittable> = (0..32)
+            .map(|_| {
+                let centre = Point3::new(
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                );
+                Sphere::new(centre, rng.gen_range(0.5..1.5), &grey).into()
+            })
+            .collect();
+
+        check_occluded_matches_hit(&BvhNode::create(spheres));
+    }
+}
@@ -1,27 +1,66 @@
-use crate::{material::ArcMaterial, ray::Ray, Point3, Vec3};
+use crate::{material::Material, ray::Ray, Point3, Real, Vec3};
+
+/// How many multiples of machine epsilon a hit location is allowed to be off by, relative to the size of the
+/// coordinates used to calculate it. This is deliberately generous, since pushing a ray's origin a tiny bit too far
+/// is invisible but not pushing it far enough causes shadow acne.
+const POSITION_ERROR_EPSILONS: Real = 32.0;
+
+/// Get the largest absolute component of a vector.
+fn max_abs(v: Vec3<Real>) -> Real {
+    v.0.abs().max(v.1.abs()).max(v.2.abs())
+}
 
 /// Struct representing the result of an object being hit.
-pub struct HitRecord {
+///
+/// The material is borrowed from the object that was hit rather than cloning its [Arc](std::sync::Arc), so creating
+/// a record doesn't need any atomic reference counting. Most records are thrown away as soon as a closer hit is found,
+/// so this keeps intersection as cheap as possible.
+#[derive(Clone)]
+pub struct HitRecord<'a> {
     /// The intersection's location in world space.
     pub hit_location: Point3,
-    /// The normal of the face that was hit.
-    pub normal: Vec3<f64>,
+    /// The shading normal of the face that was hit, which always points against the ray. This starts out the same as
+    /// `geometric_normal`, but materials like [NormalMap](crate::material::materials::NormalMap) can perturb it.
+    pub normal: Vec3<Real>,
+    /// The true normal of the face that was hit, which always points against the ray.
+    pub geometric_normal: Vec3<Real>,
+    /// A unit vector along the surface in the direction of increasing `u`, perpendicular to the normal.
+    pub tangent: Vec3<Real>,
+    /// A unit vector along the surface in the direction of increasing `v`, perpendicular to the normal and tangent.
+    pub bitangent: Vec3<Real>,
     /// The intersection point described as a fraction of the ray.
-    pub t: f64,
+    pub t: Real,
     /// Whether the ray aligns with the normal of the face.
     pub front_face: bool,
+    /// The horizontal surface coordinate of the hit, usually between 0 and 1.
+    pub u: Real,
+    /// The vertical surface coordinate of the hit, usually between 0 and 1.
+    pub v: Real,
+    /// An upper bound on how far `hit_location` could be from the true intersection because of rounding errors.
+    pub position_error: Real,
+    /// How wide the area of the surface covered by the ray is, in world units. This is 0 for rays that don't keep
+    /// track of their spread.
+    pub footprint: Real,
+    /// How far the ray's footprint stretches in `u` and `v`. This is 0 until the object sets it with
+    /// [HitRecord::set_uv_scale], which means textures should be sampled as sharply as possible.
+    pub uv_footprint: (Real, Real),
     /// The material of the hit object.
-    pub material: ArcMaterial,
+    pub material: &'a dyn Material,
 }
 
-impl HitRecord {
+impl<'a> HitRecord<'a> {
     /// Create a new HitRecord.
+    ///
+    /// The tangent should point in the direction of increasing `u`. It doesn't need to be normalised or exactly
+    /// perpendicular to the normal, but it must not be parallel to it.
     pub fn new(
         p: Point3,
-        t: f64,
+        t: Real,
         ray: &Ray,
-        outward_normal: Vec3<f64>,
-        material: ArcMaterial,
+        outward_normal: Vec3<Real>,
+        (u, v): (Real, Real),
+        tangent: Vec3<Real>,
+        material: &'a dyn Material,
     ) -> Self {
         let front_face = ray.direction.dot(&outward_normal) < 0.0;
         let normal = if front_face {
@@ -30,12 +69,109 @@ impl HitRecord {
             -outward_normal
         };
 
+        // Make the tangent frame orthonormal. The frame follows the surface's parameterisation rather than the side
+        // that was hit, so textures line up the same way from both sides.
+        let tangent = (tangent - outward_normal * outward_normal.dot(&tangent)).unit_vector();
+        let bitangent = outward_normal.cross(&tangent);
+
+        // The hit location is calculated from the ray's origin and direction, so its rounding error scales with them.
+        let position_error = POSITION_ERROR_EPSILONS
+            * Real::EPSILON
+            * (max_abs(ray.origin) + max_abs(ray.direction * t)).max(max_abs(p));
+
+        // The ray's cone is stretched across the surface the more it glances off it. This is capped so grazing hits
+        // don't blur textures away entirely.
+        let footprint = if ray.spread > 0.0 {
+            let distance = t * ray.direction.length();
+            let cosine = ray.direction.unit_vector().dot(&outward_normal).abs();
+
+            ray.spread * distance / cosine.max(0.1)
+        } else {
+            0.0
+        };
+
         Self {
             hit_location: p,
             normal,
+            geometric_normal: normal,
+            tangent,
+            bitangent,
             t,
             front_face,
+            u,
+            v,
+            position_error,
+            footprint,
+            uv_footprint: (0.0, 0.0),
             material,
         }
     }
+
+    /// Work out how far the ray's footprint stretches in surface coordinates, from the world space length of a whole
+    /// unit of `u` and `v` at the hit location.
+    ///
+    /// Objects should call this when they know how their surface coordinates are laid out, so image textures can
+    /// pick the mip level that matches the area the ray covers.
+    pub fn set_uv_scale(&mut self, u_length: Real, v_length: Real) {
+        // A length of 0, like at the poles of a sphere, squashes a whole unit into a point, so the footprint covers
+        // all of it.
+        let stretch = |length: Real| {
+            if length > 0.0 {
+                self.footprint / length
+            } else {
+                Real::INFINITY
+            }
+        };
+
+        if self.footprint > 0.0 {
+            self.uv_footprint = (stretch(u_length), stretch(v_length));
+        }
+    }
+
+    /// Create a ray leaving the surface at the hit location in a given direction.
+    ///
+    /// The ray's origin is pushed off the surface by more than the hit location's rounding error, on whichever side
+    /// of the geometric surface the ray is heading towards, so the new ray can never hit the surface it's leaving.
+    /// This is what keeps renders free of shadow acne without needing a minimum `t` for every ray, which matters a lot
+    /// more with the `f32` feature enabled.
+    pub fn spawn_ray(&self, direction: Vec3<Real>) -> Ray {
+        let offset = self.geometric_normal * self.position_error;
+
+        let origin = if direction.dot(&self.geometric_normal) > 0.0 {
+            self.hit_location + offset
+        } else {
+            self.hit_location - offset
+        };
+
+        Ray::new(origin, direction)
+    }
+
+    /// Replace the shading normal with a perturbed one, such as from a normal map, and rebuild the tangent frame
+    /// around it.
+    ///
+    /// A perturbed normal can end up facing away from the ray even though the surface itself faces it, which makes
+    /// materials send rays into the surface and leaves black pixels. To avoid this, the normal is bent back towards
+    /// the ray just enough to be visible.
+    pub fn set_shading_normal(&mut self, ray: &Ray, normal: Vec3<Real>) {
+        const MIN_COSINE: Real = 0.01;
+
+        let to_viewer = -ray.direction.unit_vector();
+        let mut normal = normal.unit_vector();
+
+        let cosine = normal.dot(&to_viewer);
+        if cosine < MIN_COSINE {
+            normal = (normal + to_viewer * (MIN_COSINE - cosine)).unit_vector();
+        }
+
+        let tangent = (self.tangent - normal * normal.dot(&self.tangent)).unit_vector();
+        let handedness = if self.bitangent.dot(&normal.cross(&tangent)) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        self.normal = normal;
+        self.tangent = tangent;
+        self.bitangent = normal.cross(&tangent) * handedness;
+    }
 }
@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
-use crate::ray::Ray;
+use rand::Rng;
+
+use num_traits::FloatConst;
+
+use crate::{light::LightBounds, ray::Ray, Point3, Real, Vec3};
 
 use super::{bounding_box::BoundingBox, HitRecord, Interval};
 
@@ -9,14 +13,116 @@ use super::{bounding_box::BoundingBox, HitRecord, Interval};
 /// Note: In order to be used by the renderer, the struct must also implement [Send] and [Sync].
 pub trait Hittable {
     /// Return a [HitRecord] of the closest point `t` along a [Ray] where `t_min < t < t_max` intersects.
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>>;
 
     fn bounding_box(&self) -> BoundingBox;
+
+    /// Return true if a [Ray] intersects anything at any point `t` where `t_min < t < t_max`.
+    ///
+    /// This is used for shadow rays, where it doesn't matter what was hit or where, only whether anything was. The
+    /// default implementation just calls [Hittable::hit], but implementors should override it to skip building a
+    /// [HitRecord] and stop at the first hit they find.
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        self.hit(ray, ray_t).is_some()
+    }
+
+    /// Return true if the object gives off light, so the renderer should sample it as a light.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Pick a random direction from `origin` towards a point on the object, for sampling it as a light.
+    ///
+    /// Returns [None] if the object can't be sampled from `origin`, which is the default.
+    fn sample_direction(&self, _origin: &Point3) -> Option<Vec3<Real>> {
+        None
+    }
+
+    /// The probability density, with respect to solid angle, of [Hittable::sample_direction] picking `direction` from
+    /// `origin`. This is zero if the direction misses the object.
+    fn direction_pdf(&self, _origin: &Point3, _direction: &Vec3<Real>) -> Real {
+        0.0
+    }
+
+    /// Bounds on where the object gives off light and which way it goes, which the renderer uses to pick the lights
+    /// that light each point the most.
+    ///
+    /// Returns [None] if the object doesn't give off light or can't tell, which is the default.
+    fn light_bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+/// Find the closest hit on an object that hasn't been cut out by its material.
+///
+/// `hit` finds the closest hit on the object within an interval. Whenever that hit has been cut out, the object is
+/// checked again just past it, so the ray carries on through the hole.
+pub(crate) fn skip_cut_outs<'a>(
+    ray_t: &Interval,
+    hit: impl Fn(&Interval) -> Option<HitRecord<'a>>,
+) -> Option<HitRecord<'a>> {
+    let mut ray_t = *ray_t;
+
+    loop {
+        let record = hit(&ray_t)?;
+        if !record.material.has_cut_outs() || !record.material.is_cut_out(&record) {
+            return Some(record);
+        }
+
+        ray_t.min = record.t.next_up();
+    }
+}
+
+/// The luminance of the light an object gives off where a ray from `origin` along `direction` first hits it.
+pub(crate) fn emitted_luminance<H: Hittable + ?Sized>(
+    object: &H,
+    origin: Point3,
+    direction: Vec3<Real>,
+) -> Real {
+    let ray = Ray::new(origin, direction);
+
+    object
+        .hit(&ray, &Interval::new(0.0, Real::INFINITY))
+        .map_or(0.0, |record| {
+            record.material.emitted(&ray, &record).luminance()
+        })
+}
+
+/// Work out the [LightBounds] of a flat object that gives off light from how bright the middle of it is on each side,
+/// where `normal` is a unit vector out of the front.
+///
+/// Only the middle is looked at, so the power is rough for lights with a texture. Returns [None] if the middle doesn't
+/// give off any light.
+pub(crate) fn flat_light_bounds<H: Hittable + ?Sized>(
+    object: &H,
+    centre: Point3,
+    normal: Vec3<Real>,
+    area: Real,
+) -> Option<LightBounds> {
+    let luminance =
+        |direction: Vec3<Real>| emitted_luminance(object, centre - direction, direction);
+    let (front, back) = (luminance(-normal), luminance(normal));
+    let bounds = LightBounds::new(object.bounding_box(), Real::PI() * area * (front + back));
+
+    match (front > 0.0, back > 0.0) {
+        (true, true) => Some(bounds),
+        (true, false) => Some(LightBounds {
+            axis: normal,
+            normal_angle: 0.0,
+            ..bounds
+        }),
+        (false, true) => Some(LightBounds {
+            axis: -normal,
+            normal_angle: 0.0,
+            ..bounds
+        }),
+        (false, false) => None,
+    }
 }
 
 // Implement hittable for a vector of objects that implement hittable.
 impl Hittable for Vec<ArcHittable> {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
         // We only need the hit record of the closest objcect.
         let mut closest_so_far = ray_t.max;
         let mut hit = None;
@@ -38,6 +144,54 @@ impl Hittable for Vec<ArcHittable> {
         hit
     }
 
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Any hit will do, so there's no need to look for the closest one.
+        self.iter().any(|object| object.occluded(ray, ray_t))
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.iter().any(|object| object.is_emissive())
+    }
+
+    /// Pick one of the objects uniformly and sample a direction towards it.
+    fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let i = rand::thread_rng().gen_range(0..self.len());
+        self[i].sample_direction(origin)
+    }
+
+    /// Any of the objects could have been picked to sample a direction, so the density is the average of them all.
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let total: Real = self
+            .iter()
+            .map(|object| object.direction_pdf(origin, direction))
+            .sum();
+
+        total / self.len() as Real
+    }
+
+    /// The bounds around all the objects that give off light, if they can all give their own bounds.
+    fn light_bounds(&self) -> Option<LightBounds> {
+        let mut bounds: Option<LightBounds> = None;
+
+        for object in self.iter().filter(|object| object.is_emissive()) {
+            let object_bounds = object.light_bounds()?;
+            bounds = Some(match bounds {
+                Some(bounds) => LightBounds::containing(&bounds, &object_bounds),
+                None => object_bounds,
+            });
+        }
+
+        bounds
+    }
+
     fn bounding_box(&self) -> BoundingBox {
         let mut ix = Interval::new(0.0, 0.0);
         let mut iy = Interval::new(0.0, 0.0);
@@ -59,3 +213,82 @@ impl Hittable for Vec<ArcHittable> {
 
 /// A Hittable trait object that can be shared between threads.
 pub type ArcHittable = Arc<dyn Hittable + Send + Sync>;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        hittable::hittables::{Quad, Sphere},
+        material::{materials::Lambertian, ArcMaterial},
+        Colour,
+    };
+
+    /// Check that [Hittable::occluded] agrees with [Hittable::hit] for rays fired at an object from all around it,
+    /// including ranges that stop just short of or just past the closest hit.
+    pub(crate) fn check_occluded_matches_hit(object: &dyn Hittable) {
+        let bounds = object.bounding_box();
+        let centre = Point3::new(
+            (bounds.x.min + bounds.x.max) / 2.0,
+            (bounds.y.min + bounds.y.max) / 2.0,
+            (bounds.z.min + bounds.z.max) / 2.0,
+        );
+        let size = bounds.x.size().max(bounds.y.size()).max(bounds.z.size());
+        let mut rng = StdRng::seed_from_u64(27);
+        let mut random_offset = |scale: Real| {
+            Vec3::new(
+                rng.gen_range(-scale..scale),
+                rng.gen_range(-scale..scale),
+                rng.gen_range(-scale..scale),
+            )
+        };
+
+        let agrees = |ray: &Ray, ray_t: &Interval| {
+            let hit = object.hit(ray, ray_t).is_some();
+            assert_eq!(object.occluded(ray, ray_t), hit);
+            hit
+        };
+
+        let (mut hits, mut misses) = (0, 0);
+        for _ in 0..10_000 {
+            // Aim past the edges of the object as well, so plenty of rays miss.
+            let origin = centre + random_offset(1.0).unit_vector() * 3.0 * size;
+            let ray = Ray::new(origin, centre + random_offset(size) - origin);
+            let ray_t = Interval::new(0.0, Real::INFINITY);
+
+            let Some(t) = object.hit(&ray, &ray_t).map(|record| record.t) else {
+                assert!(!agrees(&ray, &ray_t));
+                misses += 1;
+                continue;
+            };
+            assert!(agrees(&ray, &ray_t));
+            hits += 1;
+
+            // Nothing is closer than the closest hit, and a range that only just includes it is enough.
+            assert!(!agrees(&ray, &Interval::new(0.0, t * 0.999)));
+            assert!(agrees(&ray, &Interval::new(0.0, t * 1.001)));
+            agrees(&ray, &Interval::new(t * 1.001, Real::INFINITY));
+        }
+
+        assert!(hits > 1000 && misses > 1000, "{hits} {misses}");
+    }
+
+    #[test]
+    fn occluded_matches_hit_for_lists() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let objects: Vec<ArcHittable> = vec![
+            Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, &grey).into(),
+            Quad::new(
+                Point3::new(-1.0, -1.0, 0.5),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+                &grey,
+            )
+            .into(),
+            Sphere::new(Point3::new(1.5, 0.0, 0.0), 0.5, &grey).into(),
+        ];
+
+        check_occluded_matches_hit(&objects);
+    }
+}
@@ -1,22 +1,29 @@
 use std::sync::Arc;
 
+use num_traits::FloatConst;
+use rand::Rng;
+
 use crate::{
-    hittable::{bounding_box::BoundingBox, ArcHittable, HitRecord, Hittable, Interval},
+    hittable::{
+        bounding_box::BoundingBox, flat_light_bounds, skip_cut_outs, ArcHittable, HitRecord,
+        Hittable, Interval,
+    },
+    light::LightBounds,
     material::ArcMaterial,
     ray::Ray,
-    Point3, Vec3,
+    Point3, Real, Vec3,
 };
 
 /// Like a CD!
 pub struct Disk {
     pub center: Point3,
-    pub normal: Vec3<f64>,
-    pub radius: f64,
+    pub normal: Vec3<Real>,
+    pub radius: Real,
     pub material: ArcMaterial,
 }
 
 impl Disk {
-    pub fn new(center: Point3, normal: Vec3<f64>, radius: f64, material: &ArcMaterial) -> Self {
+    pub fn new(center: Point3, normal: Vec3<Real>, radius: Real, material: &ArcMaterial) -> Self {
         Self {
             center,
             normal,
@@ -32,14 +39,15 @@ impl Into<ArcHittable> for Disk {
     }
 }
 
-impl Hittable for Disk {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+impl Disk {
+    /// Find the point `t` along a [Ray] where `t_min < t < t_max` intersects the disk, if any.
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<Real> {
         let denom = self.normal.dot(&ray.direction);
         if denom.abs() < 1e-6 {
             return None;
         }
 
-        let t = -(ray.origin.dot(&self.normal)) / denom;
+        let t = (self.center - ray.origin).dot(&self.normal) / denom;
 
         if t < ray_t.min || ray_t.max < t {
             return None;
@@ -50,13 +58,96 @@ impl Hittable for Disk {
             return None;
         }
 
-        Some(HitRecord::new(
-            ray.at(t),
-            t,
-            ray,
-            self.normal,
-            Arc::clone(&self.material),
-        ))
+        Some(t)
+    }
+
+    /// Get the surface coordinates of a point on the disk, along with the direction of increasing `u`.
+    ///
+    /// The disk is mapped onto the unit square, so the centre is at `(0.5, 0.5)` and the edge touches each side.
+    fn uv(&self, p: Point3) -> ((Real, Real), Vec3<Real>) {
+        let (tangent, bitangent) = self.normal.unit_vector().orthonormal_basis();
+        let local = (p - self.center) / (2.0 * self.radius);
+
+        (
+            (0.5 + local.dot(&tangent), 0.5 + local.dot(&bitangent)),
+            tangent,
+        )
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
+        skip_cut_outs(ray_t, |ray_t| {
+            let t = self.intersect(ray, ray_t)?;
+            let p = ray.at(t);
+            let (uv, tangent) = self.uv(p);
+
+            let mut record = HitRecord::new(
+                p,
+                t,
+                ray,
+                self.normal.unit_vector(),
+                uv,
+                tangent,
+                self.material.as_ref(),
+            );
+            // The disk's surface coordinates span the square around it.
+            record.set_uv_scale(2.0 * self.radius, 2.0 * self.radius);
+
+            Some(record)
+        })
+    }
+
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Cut-out materials need the full hit to tell if the ray went through a hole.
+        if self.material.has_cut_outs() {
+            return self.hit(ray, ray_t).is_some();
+        }
+
+        self.intersect(ray, ray_t).is_some()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    /// Pick a point uniformly over the disk's area.
+    fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        let mut rng = rand::thread_rng();
+        let (tangent, bitangent) = self.normal.unit_vector().orthonormal_basis();
+
+        let r = self.radius * rng.gen_range(0.0..1.0 as Real).sqrt();
+        let theta = rng.gen_range(0.0..2.0 * Real::PI());
+        let point = self.center + tangent * (r * theta.cos()) + bitangent * (r * theta.sin());
+
+        Some(point - *origin)
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        let ray = Ray::new(*origin, *direction);
+        let Some(t) = self.intersect(&ray, &Interval::new(0.0, Real::INFINITY)) else {
+            return 0.0;
+        };
+
+        // Convert the density from per unit area to per unit solid angle.
+        let distance_squared = t * t * direction.length_squared();
+        let cosine = self
+            .normal
+            .unit_vector()
+            .dot(&direction.unit_vector())
+            .abs();
+        let area = Real::PI() * self.radius * self.radius;
+
+        distance_squared / (cosine * area)
+    }
+
+    fn light_bounds(&self) -> Option<LightBounds> {
+        if !self.is_emissive() {
+            return None;
+        }
+
+        let area = Real::PI() * self.radius * self.radius;
+        flat_light_bounds(self, self.center, self.normal.unit_vector(), area)
     }
 
     fn bounding_box(&self) -> BoundingBox {
@@ -64,3 +155,25 @@ impl Hittable for Disk {
         BoundingBox::new(self.center - r_vec, self.center + r_vec)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::hittable::tests::check_occluded_matches_hit, material::materials::Lambertian,
+        Colour,
+    };
+
+    #[test]
+    fn occluded_matches_hit() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let disk = Disk::new(
+            Point3::new(1.0, 2.0, 3.0),
+            Vec3::new(0.3, 1.0, -0.2),
+            1.5,
+            &grey,
+        );
+
+        check_occluded_matches_hit(&disk);
+    }
+}
@@ -1,7 +1,9 @@
 mod disk;
 mod not_hittable;
+mod quad;
 mod sphere;
 
 pub use disk::Disk;
 pub use not_hittable::NotHittable;
+pub use quad::Quad;
 pub use sphere::Sphere;
@@ -22,7 +22,12 @@ impl Hittable for NotHittable {
     }
 
     /// This will always return false.
-    fn hit(&self, _: &Ray, _: &Interval) -> Option<HitRecord> {
+    fn hit(&self, _: &Ray, _: &Interval) -> Option<HitRecord<'_>> {
         None
     }
+
+    /// This will always return false.
+    fn occluded(&self, _: &Ray, _: &Interval) -> bool {
+        false
+    }
 }
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    hittable::{
+        bounding_box::BoundingBox, flat_light_bounds, skip_cut_outs, ArcHittable, HitRecord,
+        Hittable, Interval,
+    },
+    light::LightBounds,
+    material::ArcMaterial,
+    ray::Ray,
+    Point3, Real, Vec3,
+};
+
+/// The smallest size a quad's bounding box can have along any axis. Axis aligned quads are completely flat, which
+/// would give their bounding box zero volume and confuse the BVH.
+const MIN_THICKNESS: Real = 1e-4;
+
+/// A flat parallelogram, with one corner at `corner` and its sides along `u` and `v`.
+///
+/// The front of the quad faces towards `u × v`, so the corners should go anticlockwise when looking at the front.
+pub struct Quad {
+    pub corner: Point3,
+    pub u: Vec3<Real>,
+    pub v: Vec3<Real>,
+    pub material: ArcMaterial,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3<Real>, v: Vec3<Real>, material: &ArcMaterial) -> Self {
+        Self {
+            corner,
+            u,
+            v,
+            material: Arc::clone(material),
+        }
+    }
+
+    /// The area of the quad.
+    pub fn area(&self) -> Real {
+        self.u.cross(&self.v).length()
+    }
+}
+
+impl Into<ArcHittable> for Quad {
+    fn into(self) -> ArcHittable {
+        Arc::new(self)
+    }
+}
+
+impl Quad {
+    /// Find the point `t` along a [Ray] where `t_min < t < t_max` intersects the quad, if any, along with the surface
+    /// coordinates of the hit.
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<(Real, (Real, Real))> {
+        let n = self.u.cross(&self.v);
+        let denom = n.dot(&ray.direction);
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+
+        let t = n.dot(&(self.corner - ray.origin)) / denom;
+        if t < ray_t.min || ray_t.max < t {
+            return None;
+        }
+
+        // Express the hit as a combination of the two sides, which is exactly the surface coordinates.
+        let w = n / n.length_squared();
+        let planar = ray.at(t) - self.corner;
+        let alpha = w.dot(&planar.cross(&self.v));
+        let beta = w.dot(&self.u.cross(&planar));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some((t, (alpha, beta)))
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
+        skip_cut_outs(ray_t, |ray_t| {
+            let (t, (u, v)) = self.intersect(ray, ray_t)?;
+            // Rebuild the hit from the surface coordinates so it lies exactly on the plane.
+            let p = self.corner + self.u * u + self.v * v;
+
+            let mut record = HitRecord::new(
+                p,
+                t,
+                ray,
+                self.u.cross(&self.v).unit_vector(),
+                (u, v),
+                self.u,
+                self.material.as_ref(),
+            );
+            record.set_uv_scale(self.u.length(), self.v.length());
+
+            Some(record)
+        })
+    }
+
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Cut-out materials need the full hit to tell if the ray went through a hole.
+        if self.material.has_cut_outs() {
+            return self.hit(ray, ray_t).is_some();
+        }
+
+        self.intersect(ray, ray_t).is_some()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    /// Pick a point uniformly over the quad's area.
+    fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        let mut rng = rand::thread_rng();
+        let point =
+            self.corner + self.u * rng.gen_range(0.0..1.0) + self.v * rng.gen_range(0.0..1.0);
+
+        Some(point - *origin)
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        let ray = Ray::new(*origin, *direction);
+        let Some((t, _)) = self.intersect(&ray, &Interval::new(0.0, Real::INFINITY)) else {
+            return 0.0;
+        };
+
+        // Convert the density from per unit area to per unit solid angle.
+        let distance_squared = t * t * direction.length_squared();
+        let cosine = self
+            .u
+            .cross(&self.v)
+            .unit_vector()
+            .dot(&direction.unit_vector())
+            .abs();
+
+        distance_squared / (cosine * self.area())
+    }
+
+    fn light_bounds(&self) -> Option<LightBounds> {
+        if !self.is_emissive() {
+            return None;
+        }
+
+        let centre = self.corner + (self.u + self.v) / 2.0;
+        flat_light_bounds(
+            self,
+            centre,
+            self.u.cross(&self.v).unit_vector(),
+            self.area(),
+        )
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+
+        // Build each axis directly rather than with `BoundingBox::containing`, which would treat a flat axis as empty.
+        let axis = |component: fn(&Point3) -> Real| {
+            let min = corners
+                .iter()
+                .map(component)
+                .fold(Real::INFINITY, Real::min);
+            let max = corners
+                .iter()
+                .map(component)
+                .fold(Real::NEG_INFINITY, Real::max);
+            let axis = Interval::new(min, max);
+
+            if axis.size() < MIN_THICKNESS {
+                axis.expand(MIN_THICKNESS)
+            } else {
+                axis
+            }
+        };
+
+        BoundingBox {
+            x: axis(Point3::x),
+            y: axis(Point3::y),
+            z: axis(Point3::z),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::hittable::tests::check_occluded_matches_hit, material::materials::Lambertian,
+        Colour,
+    };
+
+    #[test]
+    fn occluded_matches_hit() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let quad = Quad::new(
+            Point3::new(1.0, 2.0, 3.0),
+            Vec3::new(2.0, 0.5, 0.0),
+            Vec3::new(0.0, 0.3, 1.5),
+            &grey,
+        );
+
+        check_occluded_matches_hit(&quad);
+    }
+}
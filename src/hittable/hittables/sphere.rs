@@ -1,22 +1,29 @@
 use std::sync::Arc;
 
+use num_traits::FloatConst;
+use rand::Rng;
+
 use crate::{
-    hittable::{bounding_box::BoundingBox, ArcHittable, HitRecord, Hittable, Interval},
+    hittable::{
+        bounding_box::BoundingBox, emitted_luminance, skip_cut_outs, ArcHittable, HitRecord,
+        Hittable, Interval,
+    },
+    light::LightBounds,
     material::ArcMaterial,
     point3::Point3,
     ray::Ray,
-    Vec3,
+    Real, Vec3,
 };
 
 /// So smooth and round!
 pub struct Sphere {
     pub center: Point3,
-    pub radius: f64,
+    pub radius: Real,
     pub material: ArcMaterial,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: &ArcMaterial) -> Self {
+    pub fn new(center: Point3, radius: Real, material: &ArcMaterial) -> Self {
         Self {
             center,
             radius,
@@ -31,8 +38,9 @@ impl Into<ArcHittable> for Sphere {
     }
 }
 
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+impl Sphere {
+    /// Find the closest point `t` along a [Ray] where `t_min < t < t_max` intersects the sphere, if any.
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<Real> {
         let oc = ray.origin - self.center;
         let a = ray.direction.length_squared();
         let half_b = oc.dot(&ray.direction);
@@ -52,16 +60,143 @@ impl Hittable for Sphere {
             }
         }
 
-        let t = root;
-        let p = ray.at(t);
-        let outward_normal = (p - self.center) / self.radius;
+        Some(root)
+    }
+
+    /// Get the surface coordinates of a point on the sphere, given its outward normal.
+    ///
+    /// `u` goes around the sphere's equator starting from `-x`, and `v` goes from the bottom of the sphere to the top.
+    fn uv(outward_normal: &Vec3<Real>) -> (Real, Real) {
+        let theta = (-outward_normal.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-outward_normal.z()).atan2(outward_normal.x()) + Real::PI();
+
+        (phi / (2.0 * Real::PI()), theta / Real::PI())
+    }
+
+    /// Get the direction of increasing `u` at a point on the sphere, given its outward normal.
+    fn tangent(outward_normal: &Vec3<Real>) -> Vec3<Real> {
+        let tangent = Vec3::new(outward_normal.z(), 0.0, -outward_normal.x());
+
+        // `u` is undefined at the poles, so pick any direction along the surface there.
+        if tangent.length_squared() < 1e-12 {
+            outward_normal.orthonormal_basis().0
+        } else {
+            tangent
+        }
+    }
+
+    /// The cosine of the half-angle of the cone the sphere covers when seen from `origin`, or [None] if `origin` is
+    /// inside the sphere.
+    fn cone_cosine(&self, origin: &Point3) -> Option<Real> {
+        let distance_squared = (self.center - *origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+
+        if distance_squared <= radius_squared {
+            return None;
+        }
 
-        Some(HitRecord::new(
-            p,
-            t,
-            ray,
-            outward_normal,
-            Arc::clone(&self.material),
+        Some((1.0 - radius_squared / distance_squared).sqrt())
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord<'_>> {
+        skip_cut_outs(ray_t, |ray_t| {
+            let t = self.intersect(ray, ray_t)?;
+            let p = ray.at(t);
+            // Dividing by the radius rather than normalising flips the normal inwards for spheres with a negative
+            // radius, which makes hollow glass spheres.
+            let outward_normal = (p - self.center) / self.radius;
+
+            let mut record = HitRecord::new(
+                p,
+                t,
+                ray,
+                outward_normal,
+                Sphere::uv(&outward_normal),
+                Sphere::tangent(&outward_normal),
+                self.material.as_ref(),
+            );
+
+            // `u` goes around the circle of latitude through the hit, and `v` goes halfway round from pole to pole.
+            let latitude_radius = (1.0 - outward_normal.y() * outward_normal.y())
+                .max(0.0)
+                .sqrt();
+            record.set_uv_scale(
+                2.0 * Real::PI() * self.radius.abs() * latitude_radius,
+                Real::PI() * self.radius.abs(),
+            );
+
+            Some(record)
+        })
+    }
+
+    fn occluded(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Cut-out materials need the full hit to tell if the ray went through a hole.
+        if self.material.has_cut_outs() {
+            return self.hit(ray, ray_t).is_some();
+        }
+
+        self.intersect(ray, ray_t).is_some()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    /// Pick a direction uniformly from the cone of directions the sphere covers, which never wastes samples on the
+    /// far side of the sphere. Points inside the sphere can't be sampled.
+    fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        let cos_theta_max = self.cone_cosine(origin)?;
+        let mut rng = rand::thread_rng();
+
+        let cos_theta = 1.0 + rng.gen_range(0.0..1.0) * (cos_theta_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = rng.gen_range(0.0..2.0 * Real::PI());
+
+        let axis = (self.center - *origin).unit_vector();
+        let (tangent, bitangent) = axis.orthonormal_basis();
+
+        Some(
+            tangent * (sin_theta * phi.cos())
+                + bitangent * (sin_theta * phi.sin())
+                + axis * cos_theta,
+        )
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        let Some(cos_theta_max) = self.cone_cosine(origin) else {
+            return 0.0;
+        };
+
+        let ray = Ray::new(*origin, *direction);
+        if self
+            .intersect(&ray, &Interval::new(0.0, Real::INFINITY))
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let solid_angle = 2.0 * Real::PI() * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    /// The sphere shines in every direction, with the power worked out from how bright the top of it is.
+    fn light_bounds(&self) -> Option<LightBounds> {
+        if !self.is_emissive() {
+            return None;
+        }
+
+        let up = Vec3::new(0.0, self.radius.abs(), 0.0);
+        let luminance = emitted_luminance(self, self.center + up * 2.0, -up);
+        if luminance <= 0.0 {
+            return None;
+        }
+
+        let area = 4.0 * Real::PI() * self.radius * self.radius;
+        Some(LightBounds::new(
+            self.bounding_box(),
+            Real::PI() * area * luminance,
         ))
     }
 
@@ -70,3 +205,38 @@ impl Hittable for Sphere {
         BoundingBox::new(self.center - r_vec, self.center + r_vec)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::hittable::tests::check_occluded_matches_hit, material::materials::Lambertian,
+        Colour,
+    };
+
+    #[test]
+    fn negative_radius_flips_the_normal() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let ray_t = Interval::new(0.0, Real::INFINITY);
+
+        for radius in [2.0, -2.0] {
+            let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), radius, &grey);
+            let hit = sphere.hit(&ray, &ray_t).unwrap();
+
+            // Either way the ray hits the near side, but only the normal of the solid sphere points back at it.
+            assert!((hit.t - 3.0).abs() < 1e-6);
+            assert!((hit.hit_location - Point3::new(0.0, 0.0, 2.0)).length() < 1e-6);
+            assert_eq!(hit.front_face, radius > 0.0);
+        }
+    }
+
+    #[test]
+    fn occluded_matches_hit() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+
+        for radius in [2.0, -2.0] {
+            check_occluded_matches_hit(&Sphere::new(Point3::new(1.0, 2.0, 3.0), radius, &grey));
+        }
+    }
+}
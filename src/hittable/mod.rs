@@ -1,4 +1,7 @@
 mod bounding_box;
+mod bvh4;
+mod bvh_cache;
+mod bvh_layout;
 mod bvh_node;
 mod hit_record;
 mod hittable;
@@ -6,7 +9,10 @@ pub mod hittables;
 mod interval;
 
 pub use bounding_box::BoundingBox;
+pub use bvh4::Bvh4;
+pub use bvh_layout::{BvhChild, BvhLayout, BvhLayoutNode};
 pub use bvh_node::BvhNode;
 pub use hit_record::HitRecord;
+pub(crate) use hittable::{emitted_luminance, flat_light_bounds, skip_cut_outs};
 pub use hittable::{ArcHittable, Hittable};
 pub use interval::Interval;
@@ -1,3 +1,4 @@
+use std::io;
 use std::path::Path;
 
 use image::{ImageBuffer, Pixel, Rgb};
@@ -21,14 +22,11 @@ impl ImageEncoder for ImageCrateEncoder<Rgb<u8>> {
         *self.imgbuf.get_pixel_mut(x, y) = Rgb(colour.into());
     }
 
-    fn save_image<P>(&self, filename: P) -> Result<(), ()>
+    fn save_image<P>(&self, filename: P) -> io::Result<()>
     where
         P: AsRef<Path>,
     {
-        match self.imgbuf.save(filename) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(()),
-        }
+        self.imgbuf.save(filename).map_err(io::Error::other)
     }
 }
 
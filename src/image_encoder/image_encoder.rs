@@ -1,3 +1,4 @@
+use std::io;
 use std::path::Path;
 
 use crate::Colour;
@@ -6,7 +7,7 @@ use crate::Colour;
 pub trait ImageEncoder {
     fn new(width: u32, height: u32) -> Self;
     fn set_pixel(&mut self, x: u32, y: u32, colour: Colour);
-    fn save_image<P>(&self, filename: P) -> Result<(), ()>
+    fn save_image<P>(&self, filename: P) -> io::Result<()>
     where
         P: AsRef<Path>;
 }
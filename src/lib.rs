@@ -1,20 +1,29 @@
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
+// Every type converts into its shared pointer with `impl Into<ArcX>`, and every module keeps its trait in a file of the
+// same name next to the folder of implementations.
+#![allow(clippy::from_over_into, clippy::module_inception)]
 
+pub mod background;
 mod camera;
 mod colour;
 pub mod hittable;
 pub mod image_encoder;
+pub mod light;
 pub mod material;
 mod point3;
 mod random_world;
 mod ray;
+mod real;
 mod render;
+pub mod spectrum;
+pub mod texture;
 mod vec3;
 
 pub use camera::Camera;
 pub use colour::Colour;
 pub use point3::Point3;
 pub use random_world::random_world;
+pub use real::Real;
 pub use render::{render, RenderOptions};
 pub use vec3::Vec3;
 
@@ -26,7 +35,8 @@ mod tests {
     use test::Bencher;
 
     use crate::{
-        image_encoder::ImageEncoder, random_world, render, Camera, Point3, RenderOptions, Vec3,
+        image_encoder::ImageEncoder, random_world, render, Camera, Point3, Real, RenderOptions,
+        Vec3,
     };
 
     struct TestImageEncoder;
@@ -36,31 +46,34 @@ mod tests {
             Self
         }
 
-        fn save_image<P>(&self, _: P) -> Result<(), ()>
+        fn save_image<P>(&self, _: P) -> std::io::Result<()>
         where
             P: AsRef<Path>,
         {
             Ok(())
         }
 
-        fn set_pixel(&mut self, _: u32, _: u32, _: crate::Colour) {
-            ()
-        }
+        fn set_pixel(&mut self, _: u32, _: u32, _: crate::Colour) {}
     }
 
-    fn create_test_render_options(aspect_ratio: f64) -> RenderOptions {
+    fn create_test_render_options(aspect_ratio: Real) -> RenderOptions {
         let image_width = 60;
         RenderOptions {
-            image_width: image_width,
-            image_height: (image_width as f64 / aspect_ratio) as u32,
+            image_width,
+            image_height: (image_width as Real / aspect_ratio) as u32,
             samples: 32,
             max_bounces: 8,
             show_progress_bar: false,
             use_bvh: true,
+            use_bvh4: false,
+            bvh_cache: None,
+            spectral: false,
+            background: None,
+            lights: Vec::new(),
         }
     }
 
-    fn create_test_camera(aspect_ratio: f64) -> Camera {
+    fn create_test_camera(aspect_ratio: Real) -> Camera {
         Camera::new(
             Point3::new(13.0, 2.0, 3.0),
             Point3::new(0.0, 0.0, 0.0),
@@ -75,7 +88,7 @@ mod tests {
     #[bench]
     #[ignore]
     fn bench_random_world_no_bvh(b: &mut Bencher) {
-        const ASPECT_RATIO: f64 = 3.0 / 2.0;
+        const ASPECT_RATIO: Real = 3.0 / 2.0;
 
         let mut render_options = create_test_render_options(ASPECT_RATIO);
         render_options.use_bvh = false;
@@ -93,7 +106,7 @@ mod tests {
 
     #[bench]
     fn bench_random_world(b: &mut Bencher) {
-        const ASPECT_RATIO: f64 = 3.0 / 2.0;
+        const ASPECT_RATIO: Real = 3.0 / 2.0;
 
         let render_options = create_test_render_options(ASPECT_RATIO);
         let camera = create_test_camera(ASPECT_RATIO);
@@ -106,4 +119,22 @@ mod tests {
             );
         })
     }
+
+    #[bench]
+    fn bench_random_world_bvh4(b: &mut Bencher) {
+        const ASPECT_RATIO: Real = 3.0 / 2.0;
+
+        let mut render_options = create_test_render_options(ASPECT_RATIO);
+        render_options.use_bvh4 = true;
+
+        let camera = create_test_camera(ASPECT_RATIO);
+        let world = random_world();
+
+        b.iter(move || {
+            test::black_box(
+                render::<TestImageEncoder>(render_options.clone(), camera.clone(), world.clone())
+                    .unwrap(),
+            );
+        })
+    }
 }
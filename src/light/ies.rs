@@ -0,0 +1,236 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{Real, Vec3};
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Find where `angle` falls between the sorted `angles`, as the index of the angle before it and how far it is towards
+/// the next one. Angles outside the range are clamped to the ends.
+fn interpolate(angles: &[Real], angle: Real) -> (usize, Real) {
+    let i = angles
+        .partition_point(|&a| a <= angle)
+        .clamp(1, angles.len())
+        - 1;
+    if i + 1 >= angles.len() {
+        return (i, 0.0);
+    }
+
+    let t = (angle - angles[i]) / (angles[i + 1] - angles[i]);
+    (i, t.clamp(0.0, 1.0))
+}
+
+/// How the brightness of a real light fitting changes with direction, loaded from an IES LM-63 photometric file.
+///
+/// Manufacturers publish these for their lights, so a [SpotLight](crate::light::lights::SpotLight) can throw the same
+/// pattern as the real thing. Only type C photometry is supported, which is what almost every file uses. The angles
+/// are relative to the light, with a vertical angle of 0 straight down the middle of the light. The values are scaled
+/// so the brightest direction is 1, leaving the light's own intensity to set how bright it is.
+/// ```
+/// # use magnetite::light::IesProfile;
+/// # use magnetite::Vec3;
+/// // A light that is brightest straight down and fades out by the horizon.
+/// let profile = IesProfile::parse(
+///     "IESNA:LM-63-2002
+///     [MANUFAC] Example
+///     TILT=NONE
+///     1 1000 1 3 1 1 2 0 0 0
+///     1 1 100
+///     0 45 90
+///     0
+///     200 100 0",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(profile.value(&Vec3::new(0.0, 0.0, 1.0)), 1.0);
+/// assert!((profile.value(&Vec3::new(1.0, 0.0, 1.0).unit_vector()) - 0.5).abs() < 1e-6);
+/// assert_eq!(profile.value(&Vec3::new(0.0, 1.0, 0.0)), 0.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct IesProfile {
+    /// The angles from straight down the values were measured at, in degrees.
+    vertical_angles: Vec<Real>,
+    /// The angles around the light the values were measured at, in degrees.
+    horizontal_angles: Vec<Real>,
+    /// The brightness at each vertical angle, for each horizontal angle.
+    values: Vec<Vec<Real>>,
+}
+
+impl IesProfile {
+    /// Load a profile from an `.ies` file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Read a profile from the contents of an `.ies` file.
+    ///
+    /// Returns an [io::ErrorKind::InvalidData] error if the file is malformed or uses a kind of photometry other than
+    /// type C.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        // The header is free text up to the line giving the tilt, and everything after it is numbers.
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find(|line| line.starts_with("TILT="))
+            .ok_or_else(|| invalid_data("IES file has no TILT line"))?;
+
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                token
+                    .parse::<Real>()
+                    .map_err(|_| invalid_data("IES file has a value that isn't a number"))
+            });
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(invalid_data("IES file ended early")))
+        };
+        let mut take = |count: usize| (0..count).map(|_| next()).collect::<io::Result<Vec<_>>>();
+
+        // Tilt only matters for lamps that change with their angle in the fitting, and is skipped.
+        if tilt == "TILT=INCLUDE" {
+            let pairs = take(2)?[1];
+            take(2 * pairs as usize)?;
+        }
+
+        // The header has the number of lamps, lumens per lamp, candela multiplier, number of vertical and horizontal
+        // angles, photometric type, units and size of the fitting, then the ballast factor, a reserved value and the
+        // power it draws.
+        let header = take(13)?;
+        let (multiplier, vertical_count, horizontal_count, photometric_type, ballast_factor) =
+            (header[2], header[3], header[4], header[5], header[10]);
+
+        if photometric_type != 1.0 {
+            return Err(invalid_data("only type C IES files are supported"));
+        }
+        if vertical_count < 1.0 || horizontal_count < 1.0 {
+            return Err(invalid_data("IES file has no measurements"));
+        }
+
+        let vertical_angles = take(vertical_count as usize)?;
+        let horizontal_angles = take(horizontal_count as usize)?;
+        let values = (0..horizontal_angles.len())
+            .map(|_| take(vertical_angles.len()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let sorted = |angles: &[Real]| angles.windows(2).all(|pair| pair[0] < pair[1]);
+        if !sorted(&vertical_angles) || !sorted(&horizontal_angles) {
+            return Err(invalid_data("IES file angles aren't in increasing order"));
+        }
+
+        let scale = multiplier * ballast_factor;
+        let brightest = values
+            .iter()
+            .flatten()
+            .fold(0.0 as Real, |max, &value| max.max(value * scale));
+        if brightest <= 0.0 {
+            return Err(invalid_data("IES file doesn't give off any light"));
+        }
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            values: values
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|value| (value * scale / brightest).max(0.0))
+                        .collect()
+                })
+                .collect(),
+        })
+    }
+
+    /// The brightness of the light along `direction`, relative to its brightest direction. `direction` is a unit
+    /// vector relative to the light, where `+z` is straight down the middle of it and the horizontal angle goes from
+    /// `+x` towards `+y`.
+    pub fn value(&self, direction: &Vec3<Real>) -> Real {
+        let vertical = direction.z().clamp(-1.0, 1.0).acos().to_degrees();
+        let (first, last) = (
+            self.vertical_angles[0],
+            self.vertical_angles[self.vertical_angles.len() - 1],
+        );
+        if vertical < first || vertical > last {
+            return 0.0;
+        }
+
+        // Files only cover as much of the way around the light as they need to, and the rest is a mirror image.
+        let mut horizontal = direction
+            .y()
+            .atan2(direction.x())
+            .to_degrees()
+            .rem_euclid(360.0);
+        let widest = self.horizontal_angles[self.horizontal_angles.len() - 1];
+        if widest <= 180.0 && horizontal > 180.0 {
+            horizontal = 360.0 - horizontal;
+        }
+        if widest <= 90.0 && horizontal > 90.0 {
+            horizontal = 180.0 - horizontal;
+        }
+
+        let (h, ht) = interpolate(&self.horizontal_angles, horizontal);
+        let (v, vt) = interpolate(&self.vertical_angles, vertical);
+        let at = |h: usize, v: usize| {
+            let h = h.min(self.horizontal_angles.len() - 1);
+            let v = v.min(self.vertical_angles.len() - 1);
+            self.values[h][v]
+        };
+        let row = |h: usize| at(h, v) * (1.0 - vt) + at(h, v + 1) * vt;
+
+        row(h) * (1.0 - ht) + row(h + 1) * ht
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetry_tilt_and_errors() {
+        // A quadrant-symmetric light with tilt data, which is brighter along `x` than along `y`.
+        let profile = IesProfile::parse(
+            "IESNA91
+            TILT=INCLUDE
+            1
+            2
+            0, 90
+            1, 1
+            1 -1 2 2 2 1 2 0 0 0
+            1 1 100
+            0 90
+            0 90
+            50 50
+            25 0",
+        )
+        .unwrap();
+
+        let around = |degrees: Real| {
+            let angle = degrees.to_radians();
+            profile.value(&Vec3::new(angle.cos(), angle.sin(), 1.0).unit_vector())
+        };
+        assert!(around(0.0) > around(90.0));
+        for degrees in [0.0, 30.0, 90.0] {
+            assert!((around(degrees) - around(180.0 - degrees)).abs() < 1e-6);
+            assert!((around(degrees) - around(360.0 - degrees)).abs() < 1e-6);
+        }
+
+        // Nothing shines upwards, since the measurements stop at the horizon.
+        assert_eq!(profile.value(&Vec3::new(0.0, 0.0, -1.0)), 0.0);
+
+        for text in [
+            "no tilt line",
+            "TILT=NONE\n1 1000 1 2 1 1 2 0 0 0\n1 1 100\n0 90\n0\n100",
+            "TILT=NONE\n1 1000 1 2 1 2 2 0 0 0\n1 1 100\n0 90\n0\n100 50",
+            "TILT=NONE\n1 1000 1 2 1 1 2 0 0 0\n1 1 100\n90 0\n0\n100 50",
+        ] {
+            let error = IesProfile::parse(text).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{Colour, Point3, Real, Vec3};
+
+/// The light arriving at a point from a [Light].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSample {
+    /// The unit vector from the point towards the light.
+    pub direction: Vec3<Real>,
+    /// How far away the light is, which is infinite for lights like the sun. Anything closer than this casts a shadow.
+    pub distance: Real,
+    /// The light arriving at the point, measured on a surface facing the light.
+    pub irradiance: Colour,
+}
+
+/// Trait for lights that don't have any geometry, like a bare bulb or a spotlight.
+///
+/// These lights are infinitely small, so rays can never hit them by chance. Instead, the renderer lights every surface
+/// with them directly, sending a shadow ray towards the light to check nothing is in the way.
+pub trait Light {
+    /// The light arriving at `point`, or [None] if none of it reaches it.
+    fn sample(&self, point: &Point3) -> Option<LightSample>;
+}
+
+/// A light trait object that can be shared between threads.
+pub type ArcLight = Arc<dyn Light + Send + Sync>;
+
+// This lets render options that hold lights still derive `Debug`.
+impl fmt::Debug for dyn Light + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Light")
+    }
+}
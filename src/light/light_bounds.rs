@@ -0,0 +1,159 @@
+use num_traits::FloatConst;
+
+use crate::{
+    hittable::{BoundingBox, Interval},
+    Point3, Real, Vec3,
+};
+
+/// Bounds on where an object that gives off light is and which way the light goes.
+///
+/// These let the renderer estimate how much light a whole group of objects could shine on a point without looking at
+/// any of them, which is what its light tree is built from. The light leaves through surfaces whose normals are all
+/// within `normal_angle` of `axis`, and each surface gives off light up to `emission_angle` from its normal.
+///
+/// See "Importance Sampling of Many Lights with Adaptive Tree Splitting" (Conty Estevez & Kulla 2018).
+/// ```
+/// # use magnetite::hittable::BoundingBox;
+/// # use magnetite::light::LightBounds;
+/// # use magnetite::{Point3, Vec3};
+/// // A light in the ceiling that shines downwards.
+/// let bounds = LightBounds {
+///     axis: Vec3::new(0.0, -1.0, 0.0),
+///     normal_angle: 0.0,
+///     ..LightBounds::new(
+///         BoundingBox::new(Point3::new(-1.0, 2.0, -1.0), Point3::new(1.0, 2.0, 1.0)),
+///         1.0,
+///     )
+/// };
+///
+/// // Points closer to the light get more of it, and points above it don't get any.
+/// let near = bounds.importance(&Point3::new(0.0, 1.0, 0.0));
+/// let far = bounds.importance(&Point3::new(0.0, -5.0, 0.0));
+/// assert!(near > far);
+/// assert_eq!(bounds.importance(&Point3::new(0.0, 5.0, 0.0)), 0.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightBounds {
+    pub bounding_box: BoundingBox,
+    /// Roughly how much light is given off in total. For a diffuse light this is its luminance times its area times
+    /// π.
+    pub power: Real,
+    /// The unit vector in the middle of the cone of surface normals.
+    pub axis: Vec3<Real>,
+    /// The angle between `axis` and the edge of the cone of surface normals, in radians. This is π for lights that
+    /// shine in every direction.
+    pub normal_angle: Real,
+    /// How far from its normal each surface gives off light, in radians. This is π/2 for diffuse surfaces.
+    pub emission_angle: Real,
+}
+
+impl LightBounds {
+    /// Create bounds for a diffuse light that could shine in any direction.
+    pub fn new(bounding_box: BoundingBox, power: Real) -> Self {
+        Self {
+            bounding_box,
+            power,
+            axis: Vec3::new(0.0, 1.0, 0.0),
+            normal_angle: Real::PI(),
+            emission_angle: Real::FRAC_PI_2(),
+        }
+    }
+
+    /// Create bounds that contain two other bounds.
+    pub fn containing(a: &Self, b: &Self) -> Self {
+        if a.power <= 0.0 {
+            return *b;
+        }
+        if b.power <= 0.0 {
+            return *a;
+        }
+
+        let (axis, normal_angle) =
+            Self::cone_containing((a.axis, a.normal_angle), (b.axis, b.normal_angle));
+
+        // Lights can be completely flat along an axis, which `BoundingBox::containing` would treat as empty.
+        let (a_box, b_box) = (a.bounding_box, b.bounding_box);
+        let interval = |a: Interval, b: Interval| Interval::new(a.min.min(b.min), a.max.max(b.max));
+
+        Self {
+            bounding_box: BoundingBox {
+                x: interval(a_box.x, b_box.x),
+                y: interval(a_box.y, b_box.y),
+                z: interval(a_box.z, b_box.z),
+            },
+            power: a.power + b.power,
+            axis,
+            normal_angle,
+            emission_angle: a.emission_angle.max(b.emission_angle),
+        }
+    }
+
+    /// The smallest cone that contains two other cones, each given by its axis and the angle to its edge.
+    fn cone_containing(a: (Vec3<Real>, Real), b: (Vec3<Real>, Real)) -> (Vec3<Real>, Real) {
+        let everywhere = (a.0, Real::PI());
+        if a.1 >= Real::PI() || b.1 >= Real::PI() {
+            return everywhere;
+        }
+
+        let between = a.0.dot(&b.0).clamp(-1.0, 1.0).acos();
+        if (between + b.1).min(Real::PI()) <= a.1 {
+            return a;
+        }
+        if (between + a.1).min(Real::PI()) <= b.1 {
+            return b;
+        }
+
+        let angle = (a.1 + between + b.1) / 2.0;
+        let rotation_axis = a.0.cross(&b.0);
+        if angle >= Real::PI() || rotation_axis.length_squared() <= 0.0 {
+            return everywhere;
+        }
+
+        // Turn the first cone's axis towards the second cone's until the new cone just touches both of their edges.
+        let rotation = angle - a.1;
+        let towards_b = rotation_axis.unit_vector().cross(&a.0);
+        let axis = a.0 * rotation.cos() + towards_b * rotation.sin();
+
+        (axis.unit_vector(), angle)
+    }
+
+    /// The middle of the bounding box.
+    fn centre(&self) -> Point3 {
+        let BoundingBox { x, y, z } = self.bounding_box;
+
+        Point3::new(x.min + x.max, y.min + y.max, z.min + z.max) / 2.0
+    }
+
+    /// An estimate of how much light could reach `point`. This never says no light reaches a point when some could,
+    /// but it can be very rough when the point is close to or inside the bounds.
+    pub fn importance(&self, point: &Point3) -> Real {
+        if self.power <= 0.0 {
+            return 0.0;
+        }
+
+        // Treat the box as a sphere around its middle, and don't let points inside it get infinitely bright.
+        let BoundingBox { x, y, z } = self.bounding_box;
+        let radius = Vec3::new(x.size(), y.size(), z.size()).length() / 2.0;
+        let offset = *point - self.centre();
+        let distance = offset.length();
+        let distance_squared = (distance * distance).max(radius * radius);
+
+        // The smallest angle between any normal and any direction from the light to the point.
+        let bounds_angle = if distance > radius {
+            (radius / distance).asin()
+        } else {
+            Real::PI()
+        };
+        let point_angle = if distance > 0.0 {
+            self.axis.dot(&(offset / distance)).clamp(-1.0, 1.0).acos()
+        } else {
+            0.0
+        };
+        let angle = (point_angle - self.normal_angle - bounds_angle).max(0.0);
+        if angle >= self.emission_angle {
+            return 0.0;
+        }
+
+        self.power * angle.cos().max(0.0) / distance_squared
+    }
+}
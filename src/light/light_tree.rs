@@ -0,0 +1,300 @@
+use rand::Rng;
+
+use crate::{
+    hittable::{ArcHittable, Interval},
+    light::LightBounds,
+    ray::Ray,
+    Point3, Real, Vec3,
+};
+
+enum LightNode {
+    Leaf {
+        bounds: LightBounds,
+        light: usize,
+    },
+    Interior {
+        bounds: LightBounds,
+        children: [usize; 2],
+    },
+}
+
+impl LightNode {
+    fn bounds(&self) -> &LightBounds {
+        match self {
+            LightNode::Leaf { bounds, .. } | LightNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A tree of the objects that give off light, for picking the ones that light a point the most.
+///
+/// Each node has [LightBounds] around the lights under it. Picking a light walks down from the root, choosing each
+/// child in proportion to how much light it could give the point, so in a scene with thousands of small lights the
+/// nearby ones facing the point get most of the samples. The tree is split in the middle of its longest axis.
+pub(crate) struct LightTree {
+    lights: Vec<ArcHittable>,
+    /// The nodes of the tree, with the root first.
+    nodes: Vec<LightNode>,
+}
+
+impl LightTree {
+    /// Build a tree from objects that give off light. Objects that can't give [LightBounds] are treated as if they
+    /// give off as much light as the average of the others, in every direction.
+    pub fn new(lights: Vec<ArcHittable>) -> Self {
+        let known: Vec<Option<LightBounds>> =
+            lights.iter().map(|light| light.light_bounds()).collect();
+        let powers: Vec<Real> = known.iter().flatten().map(|bounds| bounds.power).collect();
+        let average_power = if powers.is_empty() {
+            1.0
+        } else {
+            powers.iter().sum::<Real>() / powers.len() as Real
+        };
+
+        let bounds: Vec<LightBounds> = known
+            .into_iter()
+            .zip(&lights)
+            .map(|(bounds, light)| {
+                bounds.unwrap_or_else(|| LightBounds::new(light.bounding_box(), average_power))
+            })
+            .collect();
+
+        let mut tree = Self {
+            lights,
+            nodes: Vec::new(),
+        };
+        if !bounds.is_empty() {
+            tree.build(&bounds, (0..bounds.len()).collect());
+        }
+
+        tree
+    }
+
+    /// Add the nodes for a group of lights and return the index of the node at the top.
+    fn build(&mut self, bounds: &[LightBounds], mut lights: Vec<usize>) -> usize {
+        let index = self.nodes.len();
+
+        if let [light] = lights[..] {
+            self.nodes.push(LightNode::Leaf {
+                bounds: bounds[light],
+                light,
+            });
+            return index;
+        }
+
+        // Sort the lights along the axis their middles are most spread out on and split them in half.
+        let centre = |light: usize| {
+            let b = &bounds[light].bounding_box;
+            [b.x.min + b.x.max, b.y.min + b.y.max, b.z.min + b.z.max]
+        };
+        let spread = |axis: usize| {
+            let values = lights.iter().map(|&light| centre(light)[axis]);
+            let (min, max) = values
+                .fold((Real::INFINITY, Real::NEG_INFINITY), |(min, max), value| {
+                    (min.min(value), max.max(value))
+                });
+            max - min
+        };
+        let axis = (0..3)
+            .max_by(|&a, &b| spread(a).total_cmp(&spread(b)))
+            .unwrap_or(0);
+        lights.sort_by(|&a, &b| centre(a)[axis].total_cmp(&centre(b)[axis]));
+        let right = lights.split_off(lights.len() / 2);
+
+        // Reserve this node's place so it comes before its children.
+        self.nodes.push(LightNode::Leaf {
+            bounds: bounds[lights[0]],
+            light: lights[0],
+        });
+        let children = [self.build(bounds, lights), self.build(bounds, right)];
+        self.nodes[index] = LightNode::Interior {
+            bounds: LightBounds::containing(
+                self.nodes[children[0]].bounds(),
+                self.nodes[children[1]].bounds(),
+            ),
+            children,
+        };
+
+        index
+    }
+
+    /// The chance of going down each child of a node when picking a light for `origin`, or [None] if neither of
+    /// them can light it.
+    fn child_probabilities(&self, children: &[usize; 2], origin: &Point3) -> Option<[Real; 2]> {
+        let importance = children.map(|child| self.nodes[child].bounds().importance(origin));
+        let total = importance[0] + importance[1];
+        if total <= 0.0 || !total.is_finite() {
+            return None;
+        }
+
+        Some(importance.map(|importance| importance / total))
+    }
+
+    /// Pick a light that's likely to light `origin` a lot, and a random direction towards it.
+    pub fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        let mut rng = rand::thread_rng();
+        let mut node = self.nodes.first()?;
+
+        loop {
+            match node {
+                LightNode::Leaf { light, .. } => {
+                    return self.lights[*light].sample_direction(origin)
+                }
+                LightNode::Interior { children, .. } => {
+                    let probabilities = self.child_probabilities(children, origin)?;
+                    let child = if rng.gen_range(0.0..1.0) < probabilities[0] {
+                        children[0]
+                    } else {
+                        children[1]
+                    };
+                    node = &self.nodes[child];
+                }
+            }
+        }
+    }
+
+    /// The probability density, with respect to solid angle, of [LightTree::sample_direction] picking `direction`
+    /// from `origin`. Only the parts of the tree the direction passes through need to be checked.
+    pub fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+
+        let ray = Ray::new(*origin, *direction);
+        self.node_pdf(0, &ray, 1.0)
+    }
+
+    fn node_pdf(&self, node: usize, ray: &Ray, probability: Real) -> Real {
+        match &self.nodes[node] {
+            LightNode::Leaf { light, .. } => {
+                probability * self.lights[*light].direction_pdf(&ray.origin, &ray.direction)
+            }
+            LightNode::Interior { children, .. } => {
+                let Some(probabilities) = self.child_probabilities(children, &ray.origin) else {
+                    return 0.0;
+                };
+
+                children
+                    .iter()
+                    .zip(probabilities)
+                    .filter(|&(&child, probability)| {
+                        probability > 0.0
+                            && self.nodes[child]
+                                .bounds()
+                                .bounding_box
+                                .hit(ray, &Interval::new(0.0, Real::INFINITY))
+                                .is_some()
+                    })
+                    .map(|(&child, child_probability)| {
+                        self.node_pdf(child, ray, probability * child_probability)
+                    })
+                    .sum()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::hittables::{Quad, Sphere},
+        material::{materials::DiffuseLight, ArcMaterial},
+        Colour,
+    };
+
+    /// The chance of picking each light from `origin`, found by walking every path down the tree.
+    fn light_probabilities(tree: &LightTree, origin: &Point3) -> Vec<Real> {
+        let mut probabilities = vec![0.0; tree.lights.len()];
+        let mut stack = vec![(0, 1.0)];
+
+        while let Some((node, probability)) = stack.pop() {
+            match &tree.nodes[node] {
+                LightNode::Leaf { light, .. } => probabilities[*light] += probability,
+                LightNode::Interior { children, .. } => {
+                    if let Some(child_probabilities) = tree.child_probabilities(children, origin) {
+                        for (child, child_probability) in children.iter().zip(child_probabilities) {
+                            stack.push((*child, probability * child_probability));
+                        }
+                    }
+                }
+            }
+        }
+
+        probabilities
+    }
+
+    #[test]
+    fn sampling_matches_pdf() {
+        let dim: ArcMaterial = DiffuseLight::new(Colour::new(1.0, 1.0, 1.0)).into();
+        let bright: ArcMaterial = DiffuseLight::new(Colour::new(20.0, 20.0, 20.0)).into();
+
+        // A grid of small spheres with a few quads in the ceiling, some facing away from the origin.
+        let mut lights: Vec<ArcHittable> = (0..64)
+            .map(|i| {
+                let centre = Point3::new((i % 8) as Real - 3.5, (i / 8) as Real * 0.5, -4.0);
+                let material = if i % 5 == 0 { &bright } else { &dim };
+                Sphere::new(centre, 0.1, material).into()
+            })
+            .collect();
+        for (i, facing) in [-1.0, 1.0, -1.0].into_iter().enumerate() {
+            lights.push(
+                Quad::new(
+                    Point3::new(i as Real * 2.0 - 3.0, 5.0, -1.0),
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 0.0, facing),
+                    &dim,
+                )
+                .into(),
+            );
+        }
+
+        let tree = LightTree::new(lights.clone());
+        let origin = Point3::new(0.5, 1.0, 2.0);
+        let probabilities = light_probabilities(&tree, &origin);
+        assert!((probabilities.iter().sum::<Real>() - 1.0).abs() < 1e-4);
+
+        // Quads facing away from the origin can never light it, so they're never picked.
+        assert_eq!(probabilities[64], 0.0);
+        assert!(probabilities[65] > 0.0);
+
+        // The density only checks the parts of the tree the direction passes through, but still matches adding up the
+        // density of picking the direction through every light. Which light each direction came from is counted too,
+        // apart from the odd direction that rounds off the edge of a sphere and misses it.
+        const SAMPLES: usize = 100_000;
+        let mut counts = vec![0; lights.len()];
+        let mut misses = 0;
+        for _ in 0..SAMPLES {
+            let direction = tree.sample_direction(&origin).unwrap();
+            let expected: Real = lights
+                .iter()
+                .zip(&probabilities)
+                .map(|(light, probability)| probability * light.direction_pdf(&origin, &direction))
+                .sum();
+            let pdf = tree.direction_pdf(&origin, &direction);
+            assert!(
+                (pdf - expected).abs() <= 1e-4 * expected,
+                "{pdf} {expected}"
+            );
+
+            let ray = Ray::new(origin, direction);
+            let hit = lights.iter().position(|light| {
+                light
+                    .hit(&ray, &Interval::new(0.0, Real::INFINITY))
+                    .is_some()
+            });
+            match hit {
+                Some(hit) => counts[hit] += 1,
+                None => misses += 1,
+            }
+        }
+        assert!(misses < SAMPLES / 1000, "{misses}");
+
+        for (count, probability) in counts.into_iter().zip(probabilities) {
+            let expected = probability * SAMPLES as Real;
+            assert!(
+                (count as Real - expected).abs() < 5.0 * expected.sqrt() + 5.0,
+                "{count} {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use crate::{
+    light::{ArcLight, Light, LightSample},
+    Colour, Point3, Real, Vec3,
+};
+
+/// A light infinitely far away that shines in one direction everywhere, like the sun without its size.
+/// ```
+/// # use magnetite::light::{lights::DirectionalLight, Light};
+/// # use magnetite::{Colour, Point3, Vec3};
+/// let sun = DirectionalLight::new(Vec3::new(0.0, -1.0, 0.0), Colour::new(3.0, 3.0, 3.0));
+///
+/// // The light comes from above, and nothing can be behind it.
+/// let sample = sun.sample(&Point3::new(5.0, 0.0, 0.0)).unwrap();
+/// assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+/// assert!(sample.distance.is_infinite());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// The direction the light travels in, which doesn't need to be normalised.
+    pub direction: Vec3<Real>,
+    /// How bright the light is, which is the light arriving at a surface facing it.
+    pub irradiance: Colour,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3<Real>, irradiance: Colour) -> Self {
+        Self {
+            direction,
+            irradiance,
+        }
+    }
+}
+
+impl Into<ArcLight> for DirectionalLight {
+    fn into(self) -> ArcLight {
+        Arc::new(self)
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Point3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction.unit_vector(),
+            distance: Real::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
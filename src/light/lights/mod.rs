@@ -0,0 +1,7 @@
+mod directional;
+mod point;
+mod spot;
+
+pub use directional::DirectionalLight;
+pub use point::PointLight;
+pub use spot::SpotLight;
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::{
+    light::{ArcLight, Light, LightSample},
+    Colour, Point3,
+};
+
+/// A light that shines equally in every direction from a single point, like a bare bulb.
+/// ```
+/// # use magnetite::light::{lights::PointLight, Light};
+/// # use magnetite::{Colour, Point3};
+/// let bulb = PointLight::new(Point3::new(0.0, 2.0, 0.0), Colour::new(4.0, 4.0, 4.0));
+///
+/// // Light falls off with the square of the distance.
+/// let sample = bulb.sample(&Point3::new(0.0, 0.0, 0.0)).unwrap();
+/// assert_eq!(sample.distance, 2.0);
+/// assert_eq!(sample.irradiance, Colour::new(1.0, 1.0, 1.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: Point3,
+    /// How bright the light is, which is the light arriving at a surface facing it one unit away.
+    pub intensity: Colour,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Colour) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Into<ArcLight> for PointLight {
+    fn into(self) -> ArcLight {
+        Arc::new(self)
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Point3) -> Option<LightSample> {
+        let offset = self.position - *point;
+        let distance_squared = offset.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            irradiance: self.intensity / distance_squared,
+        })
+    }
+}
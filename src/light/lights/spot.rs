@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::{
+    light::{ArcLight, IesProfile, Light, LightSample},
+    Colour, Point3, Real, Vec3,
+};
+
+/// A light that shines in a cone from a single point, like a stage light or a torch.
+///
+/// The light is full brightness out to `falloff_angle` from the middle of the cone, and then fades out smoothly until
+/// `cone_angle`. A photometric profile can shape the light inside the cone further, to match a real fitting.
+/// ```
+/// # use magnetite::light::{lights::SpotLight, Light};
+/// # use magnetite::{Colour, Point3, Real, Vec3};
+/// let spot = SpotLight {
+///     falloff_angle: (20.0 as Real).to_radians(),
+///     ..SpotLight::new(
+///         Point3::new(0.0, 1.0, 0.0),
+///         Vec3::new(0.0, -1.0, 0.0),
+///         Colour::new(1.0, 1.0, 1.0),
+///         (30.0 as Real).to_radians(),
+///     )
+/// };
+///
+/// // Straight below the light is fully lit, the edge of the cone is half lit and outside it is dark.
+/// let brightness = |x: Real| spot.sample(&Point3::new(x, 0.0, 0.0)).map_or(0.0, |s| s.irradiance.x());
+/// assert!((brightness(0.0) - 1.0).abs() < 1e-6);
+/// assert!(brightness((25.0 as Real).to_radians().tan()) > 0.3);
+/// assert!(brightness((25.0 as Real).to_radians().tan()) < 0.7);
+/// assert_eq!(brightness(1.0), 0.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotLight {
+    pub position: Point3,
+    /// The direction the middle of the cone points in, which doesn't need to be normalised.
+    pub direction: Vec3<Real>,
+    /// How bright the middle of the cone is, which is the light arriving at a surface facing it one unit away.
+    pub intensity: Colour,
+    /// The angle between the middle and the edge of the cone, in radians.
+    pub cone_angle: Real,
+    /// The angle where the light starts to fade out towards the edge of the cone, in radians. This is the same as
+    /// `cone_angle` by default, which gives a hard edge.
+    pub falloff_angle: Real,
+    /// A photometric profile, which is looked up with the middle of the cone as straight down.
+    pub profile: Option<IesProfile>,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        direction: Vec3<Real>,
+        intensity: Colour,
+        cone_angle: Real,
+    ) -> Self {
+        Self {
+            position,
+            direction,
+            intensity,
+            cone_angle,
+            falloff_angle: cone_angle,
+            profile: None,
+        }
+    }
+
+    /// How much of the light's intensity shines along `direction`, which points away from the light.
+    fn falloff(&self, direction: &Vec3<Real>) -> Real {
+        let axis = self.direction.unit_vector();
+        let cos_theta = direction.dot(&axis);
+        let (cos_cone, cos_falloff) = (self.cone_angle.cos(), self.falloff_angle.cos());
+
+        let cone = if cos_theta >= cos_falloff {
+            1.0
+        } else if cos_theta <= cos_cone {
+            0.0
+        } else {
+            let t = (cos_theta - cos_cone) / (cos_falloff - cos_cone);
+            t * t * (3.0 - 2.0 * t)
+        };
+
+        match &self.profile {
+            Some(profile) if cone > 0.0 => {
+                let (tangent, bitangent) = axis.orthonormal_basis();
+                let local = Vec3::new(
+                    direction.dot(&tangent),
+                    direction.dot(&bitangent),
+                    cos_theta,
+                );
+
+                cone * profile.value(&local)
+            }
+            _ => cone,
+        }
+    }
+}
+
+impl Into<ArcLight> for SpotLight {
+    fn into(self) -> ArcLight {
+        Arc::new(self)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Point3) -> Option<LightSample> {
+        let offset = self.position - *point;
+        let distance_squared = offset.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = offset / distance;
+        let falloff = self.falloff(&-direction);
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * (falloff / distance_squared),
+        })
+    }
+}
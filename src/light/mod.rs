@@ -0,0 +1,10 @@
+mod ies;
+mod light;
+mod light_bounds;
+mod light_tree;
+pub mod lights;
+
+pub use ies::IesProfile;
+pub use light::{ArcLight, Light, LightSample};
+pub use light_bounds::LightBounds;
+pub(crate) use light_tree::LightTree;
@@ -0,0 +1,173 @@
+use crate::{colour::Colour, hittable::HitRecord, ray::Ray, Real, Vec3};
+
+use super::{Material, MaterialRecord};
+
+/// A direction picked by [Bsdf::sample].
+#[derive(Clone, Copy, Debug)]
+pub struct BsdfSample {
+    /// The direction the light arrives from, pointing away from the surface. This doesn't need to be normalised.
+    pub direction: Vec3<Real>,
+    /// How much of the light arriving from `direction` is scattered towards the viewer. This is the BSDF times the
+    /// cosine of the angle to the normal, divided by `pdf`.
+    pub weight: Colour,
+    /// The probability density, with respect to solid angle, of picking `direction`. This has no meaning for delta
+    /// lobes.
+    pub pdf: Real,
+    /// Whether the direction came from a delta lobe, like a perfect mirror, which scatters into a single direction
+    /// and can't be evaluated by [Bsdf::eval] or [Bsdf::pdf].
+    pub is_delta: bool,
+}
+
+/// A bidirectional scattering distribution function, describing how a surface scatters light.
+///
+/// Directions are relative to the hit, with the viewer at `-ray.direction`. Every method takes the hit record so that
+/// materials can vary with the texture coordinates and the shading frame. Delta lobes are only ever returned by
+/// [Bsdf::sample], and are left out of [Bsdf::eval] and [Bsdf::pdf].
+pub trait Bsdf {
+    /// Pick a direction to continue the path in, or [None] if the ray was absorbed.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample>;
+
+    /// The BSDF times the cosine of the angle between `direction` and the normal, for the non-delta lobes.
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour;
+
+    /// The probability density, with respect to solid angle, of [Bsdf::sample] picking `direction` from the non-delta
+    /// lobes.
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real;
+
+    /// Return true if every lobe is a delta lobe at this hit. Lights aren't sampled directly for these, since
+    /// [Bsdf::eval] would always be zero.
+    fn is_delta(&self, _hit_record: &HitRecord) -> bool {
+        false
+    }
+}
+
+/// Implement [Material::scatter](super::Material::scatter) on top of a BSDF, for materials that implement both.
+pub fn scatter_bsdf(bsdf: &dyn Bsdf, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+    let sample = bsdf.sample(ray, hit_record)?;
+
+    Some(MaterialRecord {
+        attenuation: sample.weight,
+        scattered: hit_record.spawn_ray(sample.direction),
+    })
+}
+
+/// Sample a material's BSDF, or treat [Material::scatter] as a delta lobe if the material doesn't have one. This is
+/// useful for materials that wrap other materials.
+pub fn sample_material(
+    material: &dyn Material,
+    ray: &Ray,
+    hit_record: &HitRecord,
+) -> Option<BsdfSample> {
+    if let Some(bsdf) = material.bsdf() {
+        return bsdf.sample(ray, hit_record);
+    }
+
+    let MaterialRecord {
+        attenuation,
+        scattered,
+    } = material.scatter(ray, hit_record)?;
+
+    Some(BsdfSample {
+        direction: scattered.direction,
+        weight: attenuation,
+        pdf: 0.0,
+        is_delta: true,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use num_traits::FloatConst;
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::Point3;
+
+    /// A hit at the origin on a surface facing up the z axis, with its tangent along the x axis.
+    pub(crate) fn hit_record<'a>(ray: &Ray, material: &'a dyn Material) -> HitRecord<'a> {
+        HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            ray,
+            Vec3::new(0.0, 0.0, 1.0),
+            (0.5, 0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            material,
+        )
+    }
+
+    /// A ray that hits the origin travelling along `direction`.
+    pub(crate) fn ray_towards(direction: Vec3<Real>) -> Ray {
+        Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction)
+    }
+
+    /// Estimate how much of the light arriving along `direction` the material scatters, from the mean weight of its
+    /// samples. Samples that are thrown away count as absorbed.
+    pub(crate) fn scattered_fraction(material: &dyn Material, direction: Vec3<Real>) -> Colour {
+        const SAMPLES: usize = 100_000;
+
+        let ray = ray_towards(direction);
+        let hit_record = hit_record(&ray, material);
+
+        (0..SAMPLES)
+            .filter_map(|_| sample_material(material, &ray, &hit_record))
+            .fold(Colour::new(0.0, 0.0, 0.0), |total, sample| {
+                total + sample.weight
+            })
+            / SAMPLES as Real
+    }
+
+    /// Check that sampling agrees with `eval` and `pdf`, for rays hitting the front and back of the surface.
+    pub(crate) fn check_consistency(bsdf: &(dyn Material + Sync)) {
+        const SAMPLES: usize = 100_000;
+
+        for direction in [Vec3::new(0.5, 0.2, -0.8), Vec3::new(-0.3, 0.1, 0.9)] {
+            let ray = ray_towards(direction);
+            let hit_record = hit_record(&ray, bsdf);
+            let bsdf = bsdf.bsdf().unwrap();
+
+            let mut accepted = 0;
+            for _ in 0..SAMPLES {
+                let Some(sample) = bsdf.sample(&ray, &hit_record) else {
+                    continue;
+                };
+                accepted += 1;
+
+                let pdf = bsdf.pdf(&ray, &hit_record, &sample.direction);
+                let expected = bsdf.eval(&ray, &hit_record, &sample.direction) / pdf;
+                assert!((sample.pdf - pdf).abs() <= 1e-3 * pdf);
+                assert!((sample.weight - expected).length() <= 1e-3 * expected.length());
+            }
+
+            // Every sample that wasn't thrown away has to be accounted for by the density. The density is very peaked,
+            // so integrate it by sampling half the directions uniformly and the other half from the BSDF itself.
+            let accepted = accepted as Real / SAMPLES as Real;
+            let uniform_pdf = 1.0 / (4.0 * Real::PI());
+            let mut rng = rand::thread_rng();
+
+            let pdf_integral = (0..SAMPLES)
+                .map(|_| {
+                    let direction = if rng.gen_range(0.0..1.0) < 0.5 {
+                        Vec3::random_unit_vector()
+                    } else {
+                        loop {
+                            if let Some(sample) = bsdf.sample(&ray, &hit_record) {
+                                break sample.direction;
+                            }
+                        }
+                    };
+
+                    let pdf = bsdf.pdf(&ray, &hit_record, &direction);
+                    pdf / (0.5 * uniform_pdf + 0.5 * pdf / accepted)
+                })
+                .sum::<Real>()
+                / SAMPLES as Real;
+
+            assert!(
+                (pdf_integral - accepted).abs() < 0.02,
+                "{pdf_integral} {accepted}"
+            );
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Fresnel equations, giving how much light is reflected at the boundary between two materials.
+
+use num::Complex;
+use num_traits::FloatConst;
+
+use crate::{Colour, Real};
+
+/// The fraction of unpolarised light reflected by a dielectric boundary, like air to glass.
+///
+/// `cos_theta_i` is the cosine of the angle between the incoming light and the normal, and `eta` is the index of
+/// refraction on the far side of the boundary divided by the one on the near side. Returns 1 for total internal
+/// reflection.
+/// ```
+/// # use magnetite::material::fresnel::fresnel_dielectric;
+/// // Glass reflects about 4% of light head on.
+/// assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-6);
+/// // Light inside glass at a shallow angle can't get out.
+/// assert_eq!(fresnel_dielectric(0.1, 1.0 / 1.5), 1.0);
+/// ```
+pub fn fresnel_dielectric(cos_theta_i: Real, eta: Real) -> Real {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// The fraction of unpolarised light reflected by a conductor, which has a complex index of refraction `eta + k i`.
+fn fresnel_complex(cos_theta_i: Real, eta: Complex<Real>) -> Real {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+
+    let sin2_theta_t = Complex::new(sin2_theta_i, 0.0) / (eta * eta);
+    let cos_theta_t = (Complex::new(1.0, 0.0) - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (-(eta * cos_theta_t) + cos_theta_i) / (eta * cos_theta_t + cos_theta_i);
+
+    (r_parallel.norm_sqr() + r_perpendicular.norm_sqr()) / 2.0
+}
+
+/// The fraction of light reflected by a conductor like a metal, for each colour channel.
+///
+/// Conductors absorb light very quickly, which is described by the imaginary part `k` of their index of refraction.
+/// ```
+/// # use magnetite::material::fresnel::fresnel_conductor;
+/// # use magnetite::Colour;
+/// // Gold reflects more red than blue.
+/// let gold = fresnel_conductor(
+///     1.0,
+///     Colour::new(0.143119, 0.374957, 1.44248),
+///     Colour::new(3.98316, 2.38572, 1.60322),
+/// );
+/// assert!(gold.x() > gold.z());
+/// ```
+pub fn fresnel_conductor(cos_theta_i: Real, eta: Colour, k: Colour) -> Colour {
+    let channel = |eta: Real, k: Real| fresnel_complex(cos_theta_i, Complex::new(eta, k));
+
+    Colour::new(
+        channel(eta.x(), k.x()),
+        channel(eta.y(), k.y()),
+        channel(eta.z(), k.z()),
+    )
+}
+
+/// The fraction of unpolarised light reflected by a boundary with a thin transparent film on it, like a soap bubble or
+/// a coating on a lens.
+///
+/// Light reflected off the top of the film interferes with light reflected off the bottom, so the reflectance depends
+/// on the wavelength, which is what gives thin films their colours. `film_eta` and `eta` are the indices of refraction
+/// of the film and the material under it, divided by the one on the near side, and `eta` can be complex for
+/// conductors. `thickness` is the thickness of the film in wavelengths of the light.
+/// ```
+/// # use magnetite::material::fresnel::{fresnel_dielectric, fresnel_thin_film};
+/// # use magnetite::Real;
+/// # use num::Complex;
+/// // A film with no thickness does nothing.
+/// let bare = fresnel_dielectric(0.7, 1.5);
+/// assert!((fresnel_thin_film(0.7, 1.33, 0.0, Complex::new(1.5, 0.0)) - bare).abs() < 1e-6);
+///
+/// // A quarter wave coating with the right index of refraction stops glass reflecting head on.
+/// let coating = (1.5 as Real).sqrt();
+/// assert!(fresnel_thin_film(1.0, coating, 0.25 / coating, Complex::new(1.5, 0.0)) < 1e-6);
+/// ```
+pub fn fresnel_thin_film(
+    cos_theta_i: Real,
+    film_eta: Real,
+    thickness: Real,
+    eta: Complex<Real>,
+) -> Real {
+    let one = Complex::new(1.0, 0.0);
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = Complex::new(1.0 - cos_theta_i * cos_theta_i, 0.0);
+
+    // The cosine of the angle light travels at through each layer, which is imaginary past the critical angle.
+    let film_eta = Complex::new(film_eta, 0.0);
+    let cos_theta = |eta: Complex<Real>| (one - sin2_theta_i / (eta * eta)).sqrt();
+    let (cos_theta_i, cos_theta_film, cos_theta_t) = (
+        Complex::new(cos_theta_i, 0.0),
+        cos_theta(film_eta),
+        cos_theta(eta),
+    );
+
+    // The amplitude of the light reflected at a boundary, for each polarisation.
+    let perpendicular =
+        |eta_i: Complex<Real>, cos_i: Complex<Real>, eta_t: Complex<Real>, cos_t| {
+            (eta_i * cos_i - eta_t * cos_t) / (eta_i * cos_i + eta_t * cos_t)
+        };
+    let parallel = |eta_i: Complex<Real>, cos_i: Complex<Real>, eta_t: Complex<Real>, cos_t| {
+        (eta_t * cos_i - eta_i * cos_t) / (eta_t * cos_i + eta_i * cos_t)
+    };
+
+    // The phase difference picked up by light that goes through the film and back, which also makes light fade away
+    // inside the film when it can't get into it.
+    let phase = (Complex::new(0.0, 4.0 * Real::PI() * thickness) * film_eta * cos_theta_film).exp();
+
+    // Add up the light reflected off both sides of the film after any number of bounces inside it.
+    let reflectance = |top: Complex<Real>, bottom: Complex<Real>| {
+        ((top + bottom * phase) / (one + top * bottom * phase)).norm_sqr()
+    };
+
+    let r_perpendicular = reflectance(
+        perpendicular(one, cos_theta_i, film_eta, cos_theta_film),
+        perpendicular(film_eta, cos_theta_film, eta, cos_theta_t),
+    );
+    let r_parallel = reflectance(
+        parallel(one, cos_theta_i, film_eta, cos_theta_film),
+        parallel(film_eta, cos_theta_film, eta, cos_theta_t),
+    );
+
+    (r_parallel + r_perpendicular) / 2.0
+}
@@ -1,12 +1,64 @@
 use std::sync::Arc;
 
-use crate::{hittable::HitRecord, ray::Ray};
+use crate::{colour::Colour, hittable::HitRecord, ray::Ray};
 
-use super::MaterialRecord;
+use super::{Bsdf, MaterialRecord, Medium};
 
 /// Trait for a material that can either absorb a ray or scatter it.
 pub trait Material {
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord>;
+
+    /// The light given off by the material towards the ray that hit it. Most materials don't give off any light, so
+    /// this is black by default.
+    fn emitted(&self, _ray: &Ray, _hit_record: &HitRecord) -> Colour {
+        Colour::new(0.0, 0.0, 0.0)
+    }
+
+    /// Return true if the material can give off light. Objects using it are sampled as lights by the renderer.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Return true if the way the material scatters light depends on the wavelength, like glass with dispersion.
+    ///
+    /// The renderer picks a single wavelength for rays that hit these materials, if they don't carry one already, so
+    /// [Material::scatter] and [Bsdf::sample] can read it from `ray.wavelength`.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// The medium inside objects made of the material, if light scatters around inside them like it does in skin or
+    /// marble.
+    ///
+    /// Rays that hit the back of a surface with a medium have travelled through it, and the renderer follows them
+    /// from particle to particle until they get back to the surface. This assumes objects are closed and that nothing
+    /// is inside them.
+    fn medium(&self, _hit_record: &HitRecord) -> Option<Medium> {
+        None
+    }
+
+    /// Return true if parts of surfaces with the material can be cut out, like the gaps around a leaf on a quad.
+    ///
+    /// Objects check this before using a quick test for whether a shadow ray is blocked, since they need the surface
+    /// coordinates of the hit to tell if it's been cut out.
+    fn has_cut_outs(&self) -> bool {
+        false
+    }
+
+    /// Return true if the surface has been cut out at a hit, so rays should carry on through it as if it wasn't there.
+    /// This only gets called if [Material::has_cut_outs] is true.
+    fn is_cut_out(&self, _hit_record: &HitRecord) -> bool {
+        false
+    }
+
+    /// The material's [Bsdf], if it has one.
+    ///
+    /// The renderer uses the BSDF when there is one, which lets it sample lights directly and weight them against the
+    /// material's own sampling. Otherwise it falls back to [Material::scatter] and treats the material like a mirror,
+    /// so materials that only implement `scatter` still render the same as they always have.
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        None
+    }
 }
 
 /// A material trait object that can be shared between threads.
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    material::{ArcMaterial, Bsdf, Material, MaterialRecord, Medium, Parameter},
+    ray::Ray,
+    Colour, Real,
+};
+
+/// A material that cuts holes in another material wherever its opacity is below a cutoff, like the gaps around a leaf
+/// or between the wires of a fence.
+///
+/// Rays go straight through the holes, including shadow rays, so cut out parts don't cast shadows either.
+/// ```
+/// # use magnetite::material::materials::{AlphaMask, Lambertian};
+/// # use magnetite::material::{Channel, Parameter};
+/// # use magnetite::texture::textures::Checker;
+/// # use magnetite::Colour;
+/// // A lattice with square holes in it.
+/// let lattice = AlphaMask::new(
+///     Lambertian::new(Colour::new(0.5, 0.5, 0.5)),
+///     Parameter::texture(
+///         Checker::new(10.0, Colour::new(1.0, 1.0, 1.0), Colour::new(0.0, 0.0, 0.0)),
+///         Channel::Red,
+///     ),
+/// );
+/// ```
+pub struct AlphaMask {
+    pub base: ArcMaterial,
+    /// How opaque the surface is, where 0 is completely see-through and 1 is solid. Images with an alpha channel can
+    /// be loaded with [ImageTexture::open_alpha](crate::texture::textures::ImageTexture::open_alpha).
+    pub opacity: Parameter,
+    /// The opacity below which the surface is cut out.
+    pub cutoff: Real,
+}
+
+impl AlphaMask {
+    /// Create an alpha mask with a cutoff of 0.5, which is the default in glTF.
+    pub fn new(base: impl Into<ArcMaterial>, opacity: impl Into<Parameter>) -> Self {
+        Self {
+            base: base.into(),
+            opacity: opacity.into(),
+            cutoff: 0.5,
+        }
+    }
+}
+
+impl Into<ArcMaterial> for AlphaMask {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for AlphaMask {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        self.base.scatter(ray, hit_record)
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        self.base.emitted(ray, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.base.medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        true
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.opacity.value(hit_record) < self.cutoff
+            || (self.base.has_cut_outs() && self.base.is_cut_out(hit_record))
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        self.base.bsdf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{
+            hittables::{Quad, Sphere},
+            ArcHittable, BvhNode, Hittable, Interval,
+        },
+        material::materials::{Lambertian, Mix},
+        Point3, Vec3,
+    };
+
+    #[test]
+    fn cut_outs_are_skipped_by_hits_and_shadow_rays() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let hidden: ArcMaterial = AlphaMask::new(Arc::clone(&grey), 0.0).into();
+
+        // A see-through quad in front of a sphere, in a BVH so hits go through the tree.
+        let objects: Vec<ArcHittable> = vec![
+            Quad::new(
+                Point3::new(-2.0, -2.0, 1.0),
+                Vec3::new(4.0, 0.0, 0.0),
+                Vec3::new(0.0, 4.0, 0.0),
+                &hidden,
+            )
+            .into(),
+            Sphere::new(Point3::new(0.0, 0.0, -2.0), 1.0, &grey).into(),
+        ];
+        let world = BvhNode::create(objects);
+        let ray_t = Interval::new(0.0, Real::INFINITY);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = world.hit(&ray, &ray_t).unwrap();
+        assert!((record.t - 6.0).abs() < 1e-6);
+        assert!(world.occluded(&ray, &ray_t));
+
+        // Past the side of the sphere, only the cut out quad is in the way.
+        let ray = Ray::new(Point3::new(1.5, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&ray, &ray_t).is_none());
+        assert!(!world.occluded(&ray, &ray_t));
+    }
+
+    #[test]
+    fn mixes_are_cut_out_where_the_mask_has_most_weight() {
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let hidden: ArcMaterial = AlphaMask::new(Arc::clone(&grey), 0.0).into();
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+        for (weight, is_cut_out) in [(0.2, false), (0.8, true)] {
+            let mix = Mix::new(weight, Arc::clone(&grey), Arc::clone(&hidden));
+            let hit_record = HitRecord::new(
+                Point3::new(0.0, 0.0, 0.0),
+                1.0,
+                &ray,
+                Vec3::new(0.0, 0.0, 1.0),
+                (0.5, 0.5),
+                Vec3::new(1.0, 0.0, 0.0),
+                &mix,
+            );
+
+            assert!(mix.has_cut_outs());
+            assert_eq!(mix.is_cut_out(&hit_record), is_cut_out);
+        }
+    }
+}
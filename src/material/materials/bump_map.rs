@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    material::{sample_material, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, Medium},
+    ray::Ray,
+    texture::ArcTexture,
+    Colour, Real, Vec3,
+};
+
+/// The step in surface coordinates used to estimate the slope of the height texture.
+const DELTA: Real = 1e-3;
+
+/// A material that perturbs the shading normal of another material using a scalar height texture.
+///
+/// The height is the luminance of the texture, and the normal is tilted away from the direction the height increases
+/// in. Since the slope is measured in surface coordinates, how bumpy a surface looks depends on how big it is.
+pub struct BumpMap {
+    pub base: ArcMaterial,
+    pub height: ArcTexture,
+    /// How much to scale the slope of the height texture by. Negative values turn bumps into dents.
+    pub strength: Real,
+}
+
+impl BumpMap {
+    pub fn new(
+        base: impl Into<ArcMaterial>,
+        height: impl Into<ArcTexture>,
+        strength: Real,
+    ) -> Self {
+        Self {
+            base: base.into(),
+            height: height.into(),
+            strength,
+        }
+    }
+
+    fn height_at(&self, u: Real, v: Real, hit_record: &HitRecord) -> Real {
+        self.height
+            .value(u, v, &hit_record.hit_location)
+            .luminance()
+    }
+
+    /// Copy a hit record, tilting its shading normal by the slope of the height texture.
+    fn perturb<'a>(&self, ray: &Ray, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let (u, v) = (hit_record.u, hit_record.v);
+
+        // Central differences, so the bumps don't shift by half a step.
+        let dhdu = (self.height_at(u + DELTA, v, hit_record)
+            - self.height_at(u - DELTA, v, hit_record))
+            / (2.0 * DELTA);
+        let dhdv = (self.height_at(u, v + DELTA, hit_record)
+            - self.height_at(u, v - DELTA, hit_record))
+            / (2.0 * DELTA);
+
+        // The normal is flipped on the back of the surface but the tangent frame isn't, so flip the slope too.
+        let side = if hit_record.front_face { 1.0 } else { -1.0 };
+        let normal = hit_record.normal
+            - (hit_record.tangent * dhdu + hit_record.bitangent * dhdv) * (self.strength * side);
+
+        let mut hit_record = hit_record.clone();
+        hit_record.set_shading_normal(ray, normal);
+        hit_record
+    }
+}
+
+impl Into<ArcMaterial> for BumpMap {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        self.base.scatter(ray, &self.perturb(ray, hit_record))
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        self.base.emitted(ray, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.base.medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        self.base.has_cut_outs()
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.base.is_cut_out(hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        self.base.bsdf().map(|_| self as &dyn Bsdf)
+    }
+}
+
+impl Bsdf for BumpMap {
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        sample_material(self.base.as_ref(), ray, &self.perturb(ray, hit_record))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.eval(ray, &self.perturb(ray, hit_record), direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.pdf(ray, &self.perturb(ray, hit_record), direction),
+            None => 0.0,
+        }
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.is_delta(hit_record),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::materials::Lambertian, texture::Texture, Point3};
+
+    /// A height that rises along `u`.
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: Real, _: Real, _: &Point3) -> Colour {
+            Colour::new(u, u, u) * 0.5
+        }
+    }
+
+    #[test]
+    fn back_faces_are_perturbed_the_same_way() {
+        let grey = Lambertian::new(Colour::new(0.5, 0.5, 0.5));
+        let bump_map = BumpMap::new(grey, Arc::new(Ramp) as ArcTexture, 1.0);
+
+        // Hit the same point of a flat surface from either side.
+        let hit = |direction: Real| {
+            let ray = Ray::new(
+                Point3::new(0.0, 0.0, -direction),
+                Vec3::new(0.0, 0.0, direction),
+            );
+            let hit_record = HitRecord::new(
+                Point3::new(0.0, 0.0, 0.0),
+                1.0,
+                &ray,
+                Vec3::new(0.0, 0.0, 1.0),
+                (0.5, 0.5),
+                Vec3::new(1.0, 0.0, 0.0),
+                &bump_map,
+            );
+            bump_map.perturb(&ray, &hit_record).normal
+        };
+
+        // The surface slopes up towards +x, so the normal leans towards -x on both sides.
+        let (front, back) = (hit(-1.0), hit(1.0));
+        assert!(front.x() < 0.0 && front.z() > 0.0, "{front:?}");
+        assert!((front + back).length() < 1e-4, "{front:?} {back:?}");
+    }
+}
@@ -2,24 +2,37 @@ use std::sync::Arc;
 
 use crate::{
     hittable::HitRecord,
-    material::{ArcMaterial, Material, MaterialRecord},
+    material::{sample_material, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, Medium},
     ray::Ray,
-    Vec3,
+    Colour, Real, Vec3,
 };
 
 /// A checkerboard material that chooses between two other materials.
 pub struct Checkerboard {
     pub mat1: ArcMaterial,
     pub mat2: ArcMaterial,
-    pub scale: f64,
+    pub scale: Real,
 }
 
 impl Checkerboard {
-    pub fn new(scale: f64, mat1: ArcMaterial, mat2: ArcMaterial) -> Self {
+    pub fn new(scale: Real, mat1: ArcMaterial, mat2: ArcMaterial) -> Self {
         Self { mat1, mat2, scale }
     }
 }
 
+impl Checkerboard {
+    /// Pick which of the two materials to use at the hit location.
+    fn material(&self, hit_record: &HitRecord) -> &ArcMaterial {
+        let Vec3(x, y, z) = hit_record.hit_location;
+        let compute = |n: Real| Real::floor(Real::abs(n + 0.5) / self.scale) % 2.0 == 0.0;
+        if compute(x) ^ compute(y) ^ compute(z) {
+            &self.mat1
+        } else {
+            &self.mat2
+        }
+    }
+}
+
 impl Into<ArcMaterial> for Checkerboard {
     fn into(self) -> ArcMaterial {
         Arc::new(self)
@@ -28,12 +41,66 @@ impl Into<ArcMaterial> for Checkerboard {
 
 impl Material for Checkerboard {
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
-        let Vec3(x, y, z) = hit_record.hit_location;
-        let compute = |n: f64| f64::floor(f64::abs(n + 0.5) / self.scale) % 2.0 == 0.0;
-        if compute(x) ^ compute(y) ^ compute(z) {
-            self.mat1.scatter(ray, hit_record)
+        self.material(hit_record).scatter(ray, hit_record)
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        self.material(hit_record).emitted(ray, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat1.is_emissive() || self.mat2.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.mat1.is_dispersive() || self.mat2.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.material(hit_record).medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        self.mat1.has_cut_outs() || self.mat2.has_cut_outs()
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        let material = self.material(hit_record);
+        material.has_cut_outs() && material.is_cut_out(hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        if self.mat1.bsdf().is_some() && self.mat2.bsdf().is_some() {
+            Some(self)
         } else {
-            self.mat2.scatter(ray, hit_record)
+            None
+        }
+    }
+}
+
+impl Bsdf for Checkerboard {
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        sample_material(self.material(hit_record).as_ref(), ray, hit_record)
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        match self.material(hit_record).bsdf() {
+            Some(bsdf) => bsdf.eval(ray, hit_record, direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        match self.material(hit_record).bsdf() {
+            Some(bsdf) => bsdf.pdf(ray, hit_record, direction),
+            None => 0.0,
+        }
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        match self.material(hit_record).bsdf() {
+            Some(bsdf) => bsdf.is_delta(hit_record),
+            None => true,
         }
     }
 }
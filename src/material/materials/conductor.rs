@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        fresnel::fresnel_conductor,
+        microfacet::{reflect, Frame, Ggx},
+        scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord,
+    },
+    ray::Ray,
+    Colour, Real, Vec3,
+};
+
+/// A physically based metal, using a GGX microfacet distribution and the Fresnel equations for conductors.
+///
+/// The colour of a metal comes entirely from its complex index of refraction `eta + k i`, which is given for the red,
+/// green and blue channels. There are presets for some common metals.
+///
+/// Giving the bitangent a different roughness makes the metal anisotropic, like brushed metal, where highlights are
+/// stretched across the direction it was brushed in.
+/// ```
+/// # use magnetite::material::materials::Conductor;
+/// // Aluminium brushed along the surface's bitangent.
+/// let brushed = Conductor {
+///     bitangent_roughness: Some(0.1),
+///     ..Conductor::aluminium(0.5)
+/// };
+/// ```
+pub struct Conductor {
+    pub eta: Colour,
+    pub k: Colour,
+    /// The perceptual roughness along the surface's tangent, where 0 is a perfect mirror and 1 is very rough. This is
+    /// also the roughness along the bitangent unless `bitangent_roughness` is set.
+    pub roughness: Real,
+    /// The perceptual roughness along the surface's bitangent, for anisotropic metals.
+    pub bitangent_roughness: Option<Real>,
+    /// How far the tangent is rotated around the normal, in radians, which turns the direction of the anisotropy.
+    pub rotation: Real,
+}
+
+impl Conductor {
+    pub fn new(eta: Colour, k: Colour, roughness: Real) -> Self {
+        Self {
+            eta,
+            k,
+            roughness,
+            bitangent_roughness: None,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn gold(roughness: Real) -> Self {
+        Self::new(
+            Colour::new(0.143119, 0.374957, 1.44248),
+            Colour::new(3.98316, 2.38572, 1.60322),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: Real) -> Self {
+        Self::new(
+            Colour::new(0.200438, 0.924033, 1.10221),
+            Colour::new(3.91295, 2.45285, 2.14219),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: Real) -> Self {
+        Self::new(
+            Colour::new(1.65746, 0.880369, 0.521229),
+            Colour::new(9.22387, 6.26952, 4.837),
+            roughness,
+        )
+    }
+
+    fn distribution(&self) -> Ggx {
+        match self.bitangent_roughness {
+            Some(bitangent_roughness) => Ggx::anisotropic(self.roughness, bitangent_roughness),
+            None => Ggx::from_roughness(self.roughness),
+        }
+    }
+
+    /// The shading frame at a hit, turned by the rotation.
+    fn frame(&self, hit_record: &HitRecord) -> Frame {
+        Frame::new(hit_record).rotated(self.rotation)
+    }
+}
+
+impl Into<ArcMaterial> for Conductor {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Conductor {
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let frame = self.frame(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let ggx = self.distribution();
+        if ggx.is_smooth() {
+            return Some(BsdfSample {
+                direction: frame.to_world(&Vec3::new(-wo.x(), -wo.y(), wo.z())),
+                weight: fresnel_conductor(wo.z(), self.eta, self.k),
+                pdf: 0.0,
+                is_delta: true,
+            });
+        }
+
+        let m = ggx.sample_visible_normal(&wo);
+        let wi = reflect(&wo, &m);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        // Most of the microfacet BSDF cancels out with the density of visible normals.
+        let fresnel = fresnel_conductor(wo.dot(&m), self.eta, self.k);
+        let weight = fresnel * (ggx.g(&wo, &wi) / ggx.g1(&wo));
+        let pdf = ggx.visible_d(&wo, &m) / (4.0 * wo.dot(&m));
+
+        Some(BsdfSample {
+            direction: frame.to_world(&wi),
+            weight,
+            pdf,
+            is_delta: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        let frame = self.frame(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        let wi = frame.to_local(&direction.unit_vector());
+
+        let ggx = self.distribution();
+        if ggx.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Colour::new(0.0, 0.0, 0.0);
+        }
+
+        let m = (wo + wi).unit_vector();
+        fresnel_conductor(wo.dot(&m), self.eta, self.k)
+            * (ggx.d(&m) * ggx.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        let frame = self.frame(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        let wi = frame.to_local(&direction.unit_vector());
+
+        let ggx = self.distribution();
+        if ggx.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let m = (wo + wi).unit_vector();
+        ggx.visible_d(&wo, &m) / (4.0 * wo.dot(&m))
+    }
+
+    fn is_delta(&self, _: &HitRecord) -> bool {
+        self.distribution().is_smooth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::FloatConst;
+
+    use super::*;
+    use crate::material::bsdf::tests::{
+        check_consistency, hit_record, ray_towards, scattered_fraction,
+    };
+
+    #[test]
+    fn sampling_matches_pdf() {
+        check_consistency(&Conductor::gold(0.5));
+    }
+
+    #[test]
+    fn anisotropic_sampling_matches_pdf() {
+        check_consistency(&Conductor {
+            bitangent_roughness: Some(0.1),
+            rotation: 0.4,
+            ..Conductor::gold(0.6)
+        });
+    }
+
+    #[test]
+    fn nearly_smooth_metal_reflects_its_fresnel_colour() {
+        let gold = Conductor::gold(0.05);
+
+        let reflected = scattered_fraction(&gold, Vec3::new(0.0, 0.0, -1.0));
+        let expected = fresnel_conductor(1.0, gold.eta, gold.k);
+        assert!(
+            (reflected - expected).length() < 0.01,
+            "{reflected:?} {expected:?}"
+        );
+    }
+
+    #[test]
+    fn rotating_a_quarter_turn_swaps_the_roughnesses() {
+        let brushed = Conductor {
+            bitangent_roughness: Some(0.1),
+            ..Conductor::gold(0.6)
+        };
+        let rotated = Conductor {
+            roughness: 0.1,
+            bitangent_roughness: Some(0.6),
+            rotation: Real::FRAC_PI_2(),
+            ..brushed
+        };
+
+        let ray = ray_towards(Vec3::new(0.5, 0.2, -0.8));
+        for direction in [
+            Vec3::new(0.3, 0.1, 0.9),
+            Vec3::new(-0.6, 0.4, 0.7),
+            Vec3::new(0.2, -0.8, 0.5),
+        ] {
+            let a = brushed.eval(&ray, &hit_record(&ray, &brushed), &direction);
+            let b = rotated.eval(&ray, &hit_record(&ray, &rotated), &direction);
+            assert!((a - b).length() <= 1e-3 * a.length(), "{a:?} {b:?}");
+        }
+    }
+}
@@ -1,25 +1,192 @@
 use std::sync::Arc;
 
+use num::Complex;
 use rand::Rng;
 
 use crate::{
     hittable::HitRecord,
-    material::{ArcMaterial, Material, MaterialRecord},
+    material::{scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, ThinFilm},
     ray::Ray,
-    Colour,
+    Colour, Real, Vec3,
 };
 
+/// The wavelength of the sodium D line in nanometres, which is where glass catalogues measure the index of refraction.
+const D_LINE: Real = 587.6;
+
+/// How a material's index of refraction changes with the wavelength of light, which is what splits white light into a
+/// rainbow. Wavelengths are in nanometres.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    /// Cauchy's equation, `n = a + b / λ²` with `λ` in micrometres. This is simple and good enough for most glasses.
+    Cauchy { a: Real, b: Real },
+    /// The Sellmeier equation, `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)` with `λ` in micrometres. Glass catalogues list these
+    /// coefficients, and they're accurate over the whole visible range.
+    Sellmeier { b: [Real; 3], c: [Real; 3] },
+}
+
+impl Dispersion {
+    /// Fit Cauchy's equation to an index of refraction at the sodium D line and an Abbe number, which is how glasses
+    /// are usually described. Lower Abbe numbers disperse light more.
+    /// ```
+    /// # use magnetite::material::materials::Dispersion;
+    /// let dispersion = Dispersion::from_abbe_number(1.5, 40.0);
+    /// assert!((dispersion.index_of_refraction(587.6) - 1.5).abs() < 1e-6);
+    /// assert!(dispersion.index_of_refraction(450.0) > dispersion.index_of_refraction(650.0));
+    /// ```
+    pub fn from_abbe_number(index_of_refraction: Real, abbe_number: Real) -> Self {
+        // The Abbe number compares the index at the F and C hydrogen lines to the one at the D line.
+        let (f, c, d) = (0.4861, 0.6563, D_LINE / 1000.0);
+        let b = (index_of_refraction - 1.0) / (abbe_number * (1.0 / (f * f) - 1.0 / (c * c)));
+
+        Dispersion::Cauchy {
+            a: index_of_refraction - b / (d * d),
+            b,
+        }
+    }
+
+    /// Schott N-BK7, the most common optical glass.
+    /// ```
+    /// # use magnetite::material::materials::Dispersion;
+    /// assert!((Dispersion::bk7().index_of_refraction(587.6) - 1.5168).abs() < 1e-4);
+    /// ```
+    // The coefficients are copied from the catalogue, which lists them more precisely than `f32` can hold.
+    #[allow(clippy::excessive_precision)]
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    /// Diamond, which is known for its fire.
+    /// ```
+    /// # use magnetite::material::materials::Dispersion;
+    /// assert!((Dispersion::diamond().index_of_refraction(587.6) - 2.4175).abs() < 1e-3);
+    /// ```
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.175 * 0.175, 0.106 * 0.106, 0.0],
+        }
+    }
+
+    /// The index of refraction at a wavelength in nanometres.
+    pub fn index_of_refraction(&self, wavelength: Real) -> Real {
+        let micrometres = wavelength / 1000.0;
+        let l2 = micrometres * micrometres;
+
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0
+                    + b.iter()
+                        .zip(c)
+                        .map(|(b, c)| b * l2 / (l2 - c))
+                        .sum::<Real>();
+
+                n2.sqrt()
+            }
+        }
+    }
+}
+
 /// A dialectric material such as glass.
+///
+/// Light travelling through the inside of the material is absorbed following the Beer-Lambert law, which is what
+/// gives thick coloured glass its deeper colour. This assumes objects are closed, so that every ray that hits the back
+/// of a surface travelled through the inside to get there.
 pub struct Dielectric {
-    pub index_of_refraction: f64,
+    pub index_of_refraction: Real,
+    /// The absorption coefficient of each colour channel, per unit distance travelled inside the material. Black is
+    /// perfectly clear.
+    pub absorption: Colour,
+    /// How the index of refraction changes with wavelength. This replaces `index_of_refraction` when it's set.
+    pub dispersion: Option<Dispersion>,
+    /// A thin film on the surface, which makes it iridescent like a soap bubble.
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
-    pub fn new(index_of_refraction: f64) -> Self {
+    pub fn new(index_of_refraction: Real) -> Self {
         Self {
             index_of_refraction,
+            absorption: Colour::new(0.0, 0.0, 0.0),
+            dispersion: None,
+            thin_film: None,
+        }
+    }
+
+    /// Create a coloured glass, where white light that has travelled `distance` through the glass comes out as
+    /// `colour`.
+    /// ```
+    /// # use magnetite::material::materials::Dielectric;
+    /// # use magnetite::{Colour, Real};
+    /// let glass = Dielectric::coloured(1.5, Colour::new(0.5, 1.0, 1.0), 2.0);
+    /// assert!((glass.absorption.x() - (2.0 as Real).ln() / 2.0).abs() < 1e-6);
+    /// assert_eq!(glass.absorption.y(), 0.0);
+    /// ```
+    pub fn coloured(index_of_refraction: Real, colour: Colour, distance: Real) -> Self {
+        let absorb = |transmitted: Real| -transmitted.max(1e-6).ln() / distance;
+
+        Self {
+            absorption: Colour::new(absorb(colour.x()), absorb(colour.y()), absorb(colour.z())),
+            ..Self::new(index_of_refraction)
+        }
+    }
+
+    /// Create a clear material that splits light into its colours.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self {
+            dispersion: Some(dispersion),
+            ..Self::new(dispersion.index_of_refraction(D_LINE))
+        }
+    }
+
+    /// The index of refraction for a ray, which depends on its wavelength if the material disperses light.
+    fn index_of_refraction(&self, ray: &Ray) -> Real {
+        match (self.dispersion, ray.wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.index_of_refraction(wavelength),
+            _ => self.index_of_refraction,
         }
     }
+
+    /// The fraction of light of each colour reflected by the thin film on the surface.
+    fn film_reflectance(
+        &self,
+        film: &ThinFilm,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        cos_theta: Real,
+    ) -> Colour {
+        let index_of_refraction = |wavelength: Real| match self.dispersion {
+            Some(dispersion) => dispersion.index_of_refraction(wavelength),
+            None => self.index_of_refraction,
+        };
+
+        if hit_record.front_face {
+            film.reflectance(ray.wavelength, cos_theta, 1.0, |wavelength| {
+                Complex::new(index_of_refraction(wavelength), 0.0)
+            })
+        } else {
+            // Light inside the material goes through the film on its way out.
+            let outside = self.index_of_refraction(ray);
+            film.reflectance(ray.wavelength, cos_theta, outside, |_| {
+                Complex::new(1.0, 0.0)
+            })
+        }
+    }
+
+    /// The fraction of light that makes it through the inside of the material to reach a hit on the back of a surface.
+    fn transmittance(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        if hit_record.front_face {
+            return Colour::new(1.0, 1.0, 1.0);
+        }
+
+        let distance = hit_record.t * ray.direction.length();
+        let Vec3(r, g, b) = self.absorption * -distance;
+
+        Colour::new(r.exp(), g.exp(), b.exp())
+    }
 }
 
 impl Into<ArcMaterial> for Dielectric {
@@ -30,11 +197,27 @@ impl Into<ArcMaterial> for Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
-        let attenuation = Colour::new(1.0, 1.0, 1.0);
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Dielectric {
+    /// Either reflect or refract the ray, picking between them with the Fresnel reflectance so the only weight is the
+    /// light absorbed on the way through, and the colour of any thin film.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let index_of_refraction = self.index_of_refraction(ray);
         let refraction_ratio = if hit_record.front_face {
-            1.0 / self.index_of_refraction
+            1.0 / index_of_refraction
         } else {
-            self.index_of_refraction
+            index_of_refraction
         };
 
         let unit_direction = ray.direction.unit_vector();
@@ -45,23 +228,57 @@ impl Material for Dielectric {
 
         let mut rng = rand::thread_rng();
 
-        let direction =
-            if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen() {
-                unit_direction.reflect(&hit_record.normal)
-            } else {
-                unit_direction.refract(&hit_record.normal, refraction_ratio)
-            };
-
-        let scattered = Ray::new(hit_record.hit_location, direction);
-        Some(MaterialRecord {
-            attenuation,
-            scattered,
+        // A thin film reflects each colour differently, so pick between reflecting and refracting with the average
+        // reflectance and weight each colour by how likely it was to go that way.
+        let (reflect, weight) = match &self.thin_film {
+            Some(film) if !cannot_refract => {
+                let reflectance = self.film_reflectance(film, ray, hit_record, cos_theta);
+                let probability =
+                    ((reflectance.x() + reflectance.y() + reflectance.z()) / 3.0).clamp(0.0, 1.0);
+
+                if rng.gen::<Real>() < probability {
+                    (true, reflectance / probability)
+                } else {
+                    let white = Colour::new(1.0, 1.0, 1.0);
+                    (false, (white - reflectance) / (1.0 - probability))
+                }
+            }
+            _ => (
+                cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen(),
+                Colour::new(1.0, 1.0, 1.0),
+            ),
+        };
+
+        let direction = if reflect {
+            unit_direction.reflect(&hit_record.normal)
+        } else {
+            unit_direction.refract(&hit_record.normal, refraction_ratio)
+        };
+
+        Some(BsdfSample {
+            direction,
+            weight: weight * self.transmittance(ray, hit_record),
+            pdf: 0.0,
+            is_delta: true,
         })
     }
+
+    /// Both lobes are delta lobes, so there's nothing to evaluate.
+    fn eval(&self, _: &Ray, _: &HitRecord, _: &Vec3<Real>) -> Colour {
+        Colour::new(0.0, 0.0, 0.0)
+    }
+
+    fn pdf(&self, _: &Ray, _: &HitRecord, _: &Vec3<Real>) -> Real {
+        0.0
+    }
+
+    fn is_delta(&self, _: &HitRecord) -> bool {
+        true
+    }
 }
 
 impl Dielectric {
-    fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
+    fn reflectance(cosine: Real, ref_idx: Real) -> Real {
         let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
         let r0 = r0 * r0;
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
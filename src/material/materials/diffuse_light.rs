@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    material::{ArcMaterial, Material, MaterialRecord},
+    ray::Ray,
+    texture::ArcTexture,
+    Colour,
+};
+
+/// A material that gives off light evenly in every direction and doesn't reflect anything.
+///
+/// By default only the front of a surface gives off light, which is usually what's wanted for lights like a quad in
+/// the ceiling. Set `two_sided` to make both sides glow.
+pub struct DiffuseLight {
+    /// The radiance given off by the surface. This can be brighter than 1.
+    pub emit: ArcTexture,
+    pub two_sided: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: impl Into<ArcTexture>) -> Self {
+        Self {
+            emit: emit.into(),
+            two_sided: false,
+        }
+    }
+
+    /// Create a light that glows on both sides of the surface.
+    pub fn two_sided(emit: impl Into<ArcTexture>) -> Self {
+        Self {
+            emit: emit.into(),
+            two_sided: true,
+        }
+    }
+}
+
+impl Into<ArcMaterial> for DiffuseLight {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<MaterialRecord> {
+        None
+    }
+
+    fn emitted(&self, _: &Ray, hit_record: &HitRecord) -> Colour {
+        if hit_record.front_face || self.two_sided {
+            self.emit.value_at(hit_record)
+        } else {
+            Colour::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
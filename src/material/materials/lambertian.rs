@@ -1,20 +1,29 @@
 use std::sync::Arc;
 
+use num_traits::FloatConst;
+
 use crate::{
-    colour::Colour,
     hittable::HitRecord,
-    material::{ArcMaterial, Material, MaterialRecord},
+    material::{scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord},
     ray::Ray,
+    texture::ArcTexture,
     vec3::Vec3,
+    Colour, Real,
 };
 
 pub struct Lambertian {
-    pub albedo: Colour,
+    pub albedo: ArcTexture,
 }
 
 impl Lambertian {
-    pub fn new(albedo: Colour) -> Self {
-        Self { albedo }
+    pub fn new(albedo: impl Into<ArcTexture>) -> Self {
+        Self {
+            albedo: albedo.into(),
+        }
+    }
+
+    fn albedo_at(&self, hit_record: &HitRecord) -> Colour {
+        self.albedo.value_at(hit_record)
     }
 }
 
@@ -25,14 +34,71 @@ impl Into<ArcMaterial> for Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
-        let scatter_direction = hit_record.normal + Vec3::random_unit_vector();
-        let scattered = Ray::new(hit_record.hit_location, scatter_direction);
-        let attenuation = self.albedo;
-
-        Some(MaterialRecord {
-            scattered,
-            attenuation,
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Lambertian {
+    /// Scattering towards `normal` plus a random unit vector is cosine weighted, which cancels out the cosine in the
+    /// rendering equation and leaves just the albedo as the weight.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let mut direction = hit_record.normal + Vec3::random_unit_vector();
+
+        // The random vector can cancel out the normal, which would leave no direction at all.
+        if direction.length_squared() < 1e-12 {
+            direction = hit_record.normal;
+        }
+
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo_at(hit_record),
+            pdf: self.pdf(ray, hit_record, &direction),
+            is_delta: false,
         })
     }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        self.albedo_at(hit_record) * self.pdf(ray, hit_record, direction)
+    }
+
+    fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        let cosine = hit_record.normal.dot(&direction.unit_vector());
+        cosine.max(0.0) / Real::PI()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::bsdf::tests::{check_consistency, hit_record, ray_towards};
+
+    #[test]
+    fn sampling_matches_pdf() {
+        check_consistency(&Lambertian::new(Colour::new(0.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn reflects_its_albedo() {
+        const SAMPLES: usize = 100_000;
+
+        let albedo = Colour::new(0.8, 0.4, 0.2);
+        let lambertian = Lambertian::new(albedo);
+        let ray = ray_towards(Vec3::new(0.5, 0.2, -0.8));
+        let hit_record = hit_record(&ray, &lambertian);
+
+        // Integrating the BSDF times the cosine over every direction gives the fraction of light reflected.
+        let reflected = (0..SAMPLES)
+            .map(|_| lambertian.eval(&ray, &hit_record, &Vec3::random_unit_vector()))
+            .fold(Colour::new(0.0, 0.0, 0.0), |total, value| total + value)
+            * 4.0
+            * Real::PI()
+            / SAMPLES as Real;
+
+        assert!((reflected - albedo).length() < 0.01, "{reflected:?}");
+    }
 }
@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        fresnel::fresnel_dielectric,
+        microfacet::{reflect, Frame, Ggx},
+        sample_material, scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord,
+        Medium, Parameter,
+    },
+    ray::Ray,
+    texture::ArcTexture,
+    Colour, Real, Vec3,
+};
+
+/// A clear dielectric coat, like varnish or the lacquer on car paint, over any other material.
+///
+/// Light is either reflected by the top of the coat, or passes through it to the base and back out again. On the way
+/// through it's tinted by `colour`, following the Beer-Lambert law, so light that travels through the coat at a
+/// shallow angle is tinted more. Light that bounces around inside the coat is ignored, which makes the material a
+/// little darker than it should be, mostly for rough bases.
+///
+/// The coat is only on the front of the surface, so hits from behind go straight to the base.
+pub struct Layered {
+    pub base: ArcMaterial,
+    pub index_of_refraction: Real,
+    /// The perceptual roughness of the coat, where 0 is perfectly smooth and 1 is very rough.
+    pub roughness: Parameter,
+    /// The colour of light after it's gone straight through a coat that's 1 unit thick.
+    pub colour: ArcTexture,
+    pub thickness: Parameter,
+}
+
+impl Layered {
+    /// Create a smooth, clear coat over `base`.
+    pub fn new(base: ArcMaterial, index_of_refraction: Real) -> Self {
+        Self {
+            base,
+            index_of_refraction,
+            roughness: 0.0.into(),
+            colour: Colour::new(1.0, 1.0, 1.0).into(),
+            thickness: 1.0.into(),
+        }
+    }
+}
+
+/// The coat at a hit.
+struct Coat {
+    frame: Frame,
+    wo: Vec3<Real>,
+    ggx: Ggx,
+    /// The light that makes it through a coat 1 unit thick, straight down.
+    colour: Colour,
+    thickness: Real,
+    /// The probability of sampling the coat rather than the base.
+    probability: Real,
+}
+
+impl Layered {
+    /// Get the coat at a hit, or [None] if the ray hit the surface from behind.
+    fn coat(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Coat> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        if !hit_record.front_face || wo.z() <= 0.0 {
+            return None;
+        }
+
+        Some(Coat {
+            frame,
+            wo,
+            ggx: Ggx::from_roughness(self.roughness.value(hit_record)),
+            colour: self.colour.value_at(hit_record),
+            thickness: self.thickness.value(hit_record).max(0.0),
+            probability: fresnel_dielectric(wo.z(), self.index_of_refraction),
+        })
+    }
+}
+
+impl Coat {
+    /// How much of the light going in or out of the coat in the direction `w` makes it through the coat, including
+    /// light lost to the Fresnel reflection at the top of the coat. Only the path from the top of the coat to the base
+    /// is counted, so the transmittance for the whole trip is the product of the two directions.
+    fn transmittance(&self, w: &Vec3<Real>, eta: Real) -> Colour {
+        let cos_theta = w.z().abs();
+        let transmitted = 1.0 - fresnel_dielectric(cos_theta, eta);
+
+        // Beer-Lambert absorption along the refracted path through the coat.
+        let sin2_theta_t = (1.0 - cos_theta * cos_theta) / (eta * eta);
+        let cos_theta_t = (1.0 - sin2_theta_t).max(1e-6).sqrt();
+        let distance = self.thickness / cos_theta_t;
+
+        Colour::new(
+            self.colour.x().max(0.0).powf(distance),
+            self.colour.y().max(0.0).powf(distance),
+            self.colour.z().max(0.0).powf(distance),
+        ) * transmitted
+    }
+
+    /// How much light makes it from `wi`, through the coat to the base and back out to the viewer. Light that goes
+    /// into the surface, through a transmissive base, only crosses the coat once.
+    fn round_trip(&self, wi: &Vec3<Real>, eta: Real) -> Colour {
+        let transmittance = self.transmittance(&self.wo, eta);
+
+        if wi.z() > 0.0 {
+            transmittance * self.transmittance(wi, eta)
+        } else {
+            transmittance
+        }
+    }
+
+    /// The coat's reflection times the cosine, and the density of sampling it, in the local frame.
+    fn eval_pdf(&self, wi: &Vec3<Real>, eta: Real) -> (Real, Real) {
+        let wo = self.wo;
+        if self.ggx.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let m = (wo + *wi).unit_vector();
+        let value = fresnel_dielectric(wo.dot(&m), eta) * self.ggx.d(&m) * self.ggx.g(&wo, wi)
+            / (4.0 * wo.z());
+        let pdf = self.ggx.visible_d(&wo, &m) / (4.0 * wo.dot(&m));
+
+        (value, pdf)
+    }
+}
+
+impl Into<ArcMaterial> for Layered {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for Layered {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    /// Light given off by the base is tinted by the coat on its way out.
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        let emitted = self.base.emitted(ray, hit_record);
+
+        match self.coat(ray, hit_record) {
+            Some(coat) => emitted * coat.transmittance(&coat.wo, self.index_of_refraction),
+            None => emitted,
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.base.medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        self.base.has_cut_outs()
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.base.is_cut_out(hit_record)
+    }
+
+    /// A base without a BSDF is treated as a delta lobe, so lights can still be sampled for a rough coat.
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Layered {
+    /// Pick the coat with the probability of it reflecting the ray, otherwise sample the base and tint it by the
+    /// coat.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let Some(coat) = self.coat(ray, hit_record) else {
+            return sample_material(self.base.as_ref(), ray, hit_record);
+        };
+
+        let sample = if rand::thread_rng().gen_range(0.0..1.0) < coat.probability {
+            if coat.ggx.is_smooth() {
+                // The probability is exactly the coat's reflectance, so it cancels out.
+                return Some(BsdfSample {
+                    direction: coat.frame.to_world(&Vec3::new(
+                        -coat.wo.x(),
+                        -coat.wo.y(),
+                        coat.wo.z(),
+                    )),
+                    weight: Colour::new(1.0, 1.0, 1.0),
+                    pdf: 0.0,
+                    is_delta: true,
+                });
+            }
+
+            let wi = reflect(&coat.wo, &coat.ggx.sample_visible_normal(&coat.wo));
+            if wi.z() <= 0.0 {
+                return None;
+            }
+
+            BsdfSample {
+                direction: coat.frame.to_world(&wi),
+                weight: Colour::new(0.0, 0.0, 0.0),
+                pdf: 0.0,
+                is_delta: false,
+            }
+        } else {
+            let sample = sample_material(self.base.as_ref(), ray, hit_record)?;
+            if sample.is_delta {
+                let wi = coat.frame.to_local(&sample.direction.unit_vector());
+                let transmittance = coat.round_trip(&wi, self.index_of_refraction);
+
+                return Some(BsdfSample {
+                    weight: sample.weight * transmittance / (1.0 - coat.probability),
+                    ..sample
+                });
+            }
+
+            sample
+        };
+
+        let pdf = self.pdf(ray, hit_record, &sample.direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            weight: self.eval(ray, hit_record, &sample.direction) / pdf,
+            pdf,
+            ..sample
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        let base = match self.base.bsdf() {
+            Some(bsdf) => bsdf.eval(ray, hit_record, direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        };
+
+        let Some(coat) = self.coat(ray, hit_record) else {
+            return base;
+        };
+
+        let wi = coat.frame.to_local(&direction.unit_vector());
+        let transmittance = coat.round_trip(&wi, self.index_of_refraction);
+        let (reflected, _) = coat.eval_pdf(&wi, self.index_of_refraction);
+
+        Colour::new(1.0, 1.0, 1.0) * reflected + base * transmittance
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        let base = match self.base.bsdf() {
+            Some(bsdf) => bsdf.pdf(ray, hit_record, direction),
+            None => 0.0,
+        };
+
+        let Some(coat) = self.coat(ray, hit_record) else {
+            return base;
+        };
+
+        let wi = coat.frame.to_local(&direction.unit_vector());
+        let (_, reflected) = coat.eval_pdf(&wi, self.index_of_refraction);
+
+        coat.probability * reflected + (1.0 - coat.probability) * base
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        let base_is_delta = match self.base.bsdf() {
+            Some(bsdf) => bsdf.is_delta(hit_record),
+            None => true,
+        };
+
+        // Rough coats are only on the front of the surface.
+        let coat_is_delta = !hit_record.front_face
+            || Ggx::from_roughness(self.roughness.value(hit_record)).is_smooth();
+
+        base_is_delta && coat_is_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{
+        bsdf::tests::{check_consistency, scattered_fraction},
+        materials::{Lambertian, RoughDielectric},
+    };
+
+    #[test]
+    fn sampling_matches_pdf() {
+        let mut varnish = Layered::new(Lambertian::new(Colour::new(0.8, 0.4, 0.2)).into(), 1.5);
+        varnish.roughness = 0.3.into();
+        varnish.colour = Colour::new(0.9, 0.8, 0.6).into();
+        check_consistency(&varnish);
+
+        let mut coated_glass = Layered::new(RoughDielectric::new(1.5, 0.4).into(), 1.4);
+        coated_glass.roughness = 0.2.into();
+        check_consistency(&coated_glass);
+    }
+
+    #[test]
+    fn coat_never_adds_energy() {
+        let white: ArcMaterial = Lambertian::new(Colour::new(1.0, 1.0, 1.0)).into();
+
+        for roughness in [0.0, 0.3] {
+            let mut varnish = Layered::new(white.clone(), 1.5);
+            varnish.roughness = roughness.into();
+
+            // A clear coat over a white base can only lose light, but most of it still makes it back out.
+            for direction in [Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.8, 0.2, -0.3)] {
+                let reflected = scattered_fraction(&varnish, direction).x();
+                assert!(reflected <= 1.0, "{roughness} {reflected}");
+                assert!(reflected > 0.7, "{roughness} {reflected}");
+            }
+        }
+    }
+}
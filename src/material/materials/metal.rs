@@ -1,24 +1,90 @@
 use std::sync::Arc;
 
+use num::Complex;
+use num_traits::FloatConst;
+
 use crate::{
-    colour::Colour,
     hittable::HitRecord,
-    material::{ArcMaterial, Material, MaterialRecord},
+    material::{scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, ThinFilm},
     ray::Ray,
+    spectrum,
+    texture::ArcTexture,
     vec3::Vec3,
+    Colour, Real,
 };
 
 pub struct Metal {
-    pub albedo: Colour,
-    pub fuzziness: f64,
+    pub albedo: ArcTexture,
+    pub fuzziness: Real,
+    /// A thin film on the surface, like the oxide layer on anodised or heat tinted metal. The albedo is then how much
+    /// light the bare metal reflects head on, and it reflects more at grazing angles like a real metal.
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Metal {
-    pub fn new(albedo: Colour, fuzziness: f64) -> Self {
-        Self { albedo, fuzziness }
+    pub fn new(albedo: impl Into<ArcTexture>, fuzziness: Real) -> Self {
+        Self {
+            albedo: albedo.into(),
+            fuzziness,
+            thin_film: None,
+        }
+    }
+
+    fn albedo_at(&self, hit_record: &HitRecord) -> Colour {
+        self.albedo.value_at(hit_record)
+    }
+
+    /// The fraction of light of each colour reflected towards the viewer.
+    fn reflectance(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        let albedo = self.albedo_at(hit_record);
+        let Some(film) = &self.thin_film else {
+            return albedo;
+        };
+
+        let cos_theta = -ray.direction.unit_vector().dot(&hit_record.normal);
+        film.reflectance(ray.wavelength, cos_theta, 1.0, |wavelength| {
+            conductor_index_of_refraction(spectrum::uplift(&albedo, wavelength))
+        })
+    }
+
+    /// The density of a fuzzy reflection picking `direction`.
+    ///
+    /// Fuzzy reflections pick a random point in a ball of radius `fuzziness` around the tip of the perfect reflection,
+    /// so the density of a direction is how much of the ball lies along it, weighted by the square of the distance.
+    fn fuzz_pdf(&self, reflected: &Vec3<Real>, direction: &Vec3<Real>) -> Real {
+        let direction = direction.unit_vector();
+        let radius = self.fuzziness;
+
+        // Find where a ray from the surface in `direction` enters and leaves the ball.
+        let b = direction.dot(reflected);
+        let discriminant = b * b - (1.0 - radius * radius);
+        if discriminant < 0.0 {
+            return 0.0;
+        }
+
+        let sqrtd = discriminant.sqrt();
+        let far = b + sqrtd;
+        let near = (b - sqrtd).max(0.0);
+        if far <= 0.0 {
+            return 0.0;
+        }
+
+        (far.powi(3) - near.powi(3)) / (4.0 * Real::PI() * radius.powi(3))
     }
 }
 
+/// The complex index of refraction of a metal that reflects `reflectance` of the light hitting it head on.
+///
+/// This is the mapping from "Artist Friendly Metallic Fresnel" (Gulbrandsen 2014), with the edge tint set to the
+/// reflectance.
+fn conductor_index_of_refraction(reflectance: Real) -> Complex<Real> {
+    let r = reflectance.clamp(0.0, 0.999);
+    let eta = r * (1.0 - r) / (1.0 + r) + (1.0 - r) * (1.0 + r.sqrt()) / (1.0 - r.sqrt());
+    let k2 = (r * (eta + 1.0) * (eta + 1.0) - (eta - 1.0) * (eta - 1.0)) / (1.0 - r);
+
+    Complex::new(eta, k2.max(0.0).sqrt())
+}
+
 impl Into<ArcMaterial> for Metal {
     fn into(self) -> ArcMaterial {
         Arc::new(self)
@@ -27,20 +93,69 @@ impl Into<ArcMaterial> for Metal {
 
 impl Material for Metal {
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Metal {
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
         let reflected = ray.direction.unit_vector().reflect(&hit_record.normal);
-        let scattered = Ray::new(
-            hit_record.hit_location,
-            reflected + Vec3::random_in_unit_sphere() * self.fuzziness,
-        );
-        let attenuation = self.albedo;
-
-        if scattered.direction.dot(&hit_record.normal) > 0.0 {
-            Some(MaterialRecord {
-                attenuation,
-                scattered,
-            })
+        let mut direction = reflected + Vec3::random_in_unit_sphere() * self.fuzziness;
+
+        // With a perturbed shading normal, the reflection can end up below the actual surface. Mirror it back above
+        // the surface rather than absorbing it, which would leave black pixels.
+        let geometric_normal = hit_record.geometric_normal;
+        if hit_record.normal != geometric_normal && direction.dot(&geometric_normal) <= 0.0 {
+            direction = direction.reflect(&geometric_normal);
+        }
+
+        if direction.dot(&geometric_normal) <= 0.0 {
+            return None;
+        }
+
+        let is_delta = self.is_delta(hit_record);
+        let pdf = if is_delta {
+            0.0
         } else {
-            None
+            self.pdf(ray, hit_record, &direction)
+        };
+
+        Some(BsdfSample {
+            direction,
+            weight: self.reflectance(ray, hit_record),
+            pdf,
+            is_delta,
+        })
+    }
+
+    /// Fuzzy reflections aren't physically based, so they're defined as whatever gives every sample a weight of the
+    /// albedo, which is how the metal has always looked. A thin film replaces the albedo with its reflectance.
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        self.reflectance(ray, hit_record) * self.pdf(ray, hit_record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        let geometric_normal = hit_record.geometric_normal;
+        if self.is_delta(hit_record) || direction.dot(&geometric_normal) <= 0.0 {
+            return 0.0;
         }
+
+        let reflected = ray.direction.unit_vector().reflect(&hit_record.normal);
+        let mut pdf = self.fuzz_pdf(&reflected, direction);
+
+        // Directions can also be reached by mirroring one that started out below the surface.
+        if hit_record.normal != geometric_normal {
+            pdf += self.fuzz_pdf(&reflected, &direction.reflect(&geometric_normal));
+        }
+
+        pdf
+    }
+
+    fn is_delta(&self, _: &HitRecord) -> bool {
+        self.fuzziness <= 0.0
     }
 }
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        sample_material, scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord,
+        Medium, Parameter,
+    },
+    ray::Ray,
+    Colour, Real, Vec3,
+};
+
+/// A blend of two materials, like a mask of rust over paint.
+///
+/// Where `weight` is 0 the material looks like `mat1`, and where it's 1 it looks like `mat2`. Use
+/// [Parameter::texture] to blend with a mask.
+///
+/// Cut-outs and media can't be blended, so they come from whichever material has the most weight at each hit.
+pub struct Mix {
+    pub mat1: ArcMaterial,
+    pub mat2: ArcMaterial,
+    pub weight: Parameter,
+}
+
+impl Mix {
+    pub fn new(weight: impl Into<Parameter>, mat1: ArcMaterial, mat2: ArcMaterial) -> Self {
+        Self {
+            mat1,
+            mat2,
+            weight: weight.into(),
+        }
+    }
+}
+
+impl Mix {
+    /// The weight of `mat2` at the hit location, between 0 and 1.
+    fn weight(&self, hit_record: &HitRecord) -> Real {
+        self.weight.value(hit_record).clamp(0.0, 1.0)
+    }
+
+    /// The material with the most weight at the hit location.
+    fn dominant(&self, hit_record: &HitRecord) -> &ArcMaterial {
+        if self.weight(hit_record) > 0.5 {
+            &self.mat2
+        } else {
+            &self.mat1
+        }
+    }
+}
+
+impl Into<ArcMaterial> for Mix {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for Mix {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        let weight = self.weight(hit_record);
+
+        self.mat1.emitted(ray, hit_record) * (1.0 - weight)
+            + self.mat2.emitted(ray, hit_record) * weight
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat1.is_emissive() || self.mat2.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.mat1.is_dispersive() || self.mat2.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.dominant(hit_record).medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        self.mat1.has_cut_outs() || self.mat2.has_cut_outs()
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        let material = self.dominant(hit_record);
+        material.has_cut_outs() && material.is_cut_out(hit_record)
+    }
+
+    /// Materials without a BSDF are treated as delta lobes, so the other material can still have lights sampled for it.
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Mix {
+    /// Pick one of the materials with the probability of its weight. Directions from delta lobes only come from the
+    /// material that was picked, and the chance of picking it cancels out its weight in the mix, so their weight is
+    /// left as it is. Everything else is weighted against both materials.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let weight = self.weight(hit_record);
+        let material = if rand::thread_rng().gen_range(0.0..1.0) < weight {
+            &self.mat2
+        } else {
+            &self.mat1
+        };
+
+        let sample = sample_material(material.as_ref(), ray, hit_record)?;
+        if sample.is_delta {
+            return Some(sample);
+        }
+
+        let pdf = self.pdf(ray, hit_record, &sample.direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            weight: self.eval(ray, hit_record, &sample.direction) / pdf,
+            pdf,
+            ..sample
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        let weight = self.weight(hit_record);
+        let eval = |material: &ArcMaterial| match material.bsdf() {
+            Some(bsdf) => bsdf.eval(ray, hit_record, direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        };
+
+        eval(&self.mat1) * (1.0 - weight) + eval(&self.mat2) * weight
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        let weight = self.weight(hit_record);
+        let pdf = |material: &ArcMaterial| match material.bsdf() {
+            Some(bsdf) => bsdf.pdf(ray, hit_record, direction),
+            None => 0.0,
+        };
+
+        pdf(&self.mat1) * (1.0 - weight) + pdf(&self.mat2) * weight
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        let weight = self.weight(hit_record);
+        let is_delta = |material: &ArcMaterial| match material.bsdf() {
+            Some(bsdf) => bsdf.is_delta(hit_record),
+            None => true,
+        };
+
+        (weight >= 1.0 || is_delta(&self.mat1)) && (weight <= 0.0 || is_delta(&self.mat2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{
+        bsdf::tests::{check_consistency, hit_record, ray_towards, scattered_fraction},
+        materials::{Conductor, Dielectric, Lambertian, Metal},
+    };
+
+    #[test]
+    fn sampling_matches_pdf() {
+        check_consistency(&Mix::new(
+            0.3,
+            Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into(),
+            Conductor::gold(0.3).into(),
+        ));
+    }
+
+    #[test]
+    fn weights_follow_the_factor() {
+        let black: ArcMaterial = Lambertian::new(Colour::new(0.0, 0.0, 0.0)).into();
+        let white: ArcMaterial = Lambertian::new(Colour::new(1.0, 1.0, 1.0)).into();
+
+        for weight in [0.0, 0.3, 0.75, 1.0] {
+            let mix = Mix::new(weight, black.clone(), white.clone());
+            let reflected = scattered_fraction(&mix, Vec3::new(0.5, 0.2, -0.8));
+            assert!(
+                (reflected.x() - weight).abs() < 0.01,
+                "{weight} {reflected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn mix_of_delta_materials_conserves_energy() {
+        const SAMPLES: usize = 100_000;
+
+        let mirror_and_glass = Mix::new(
+            0.5,
+            Metal::new(Colour::new(0.8, 0.8, 0.8), 0.0).into(),
+            Dielectric::new(1.5).into(),
+        );
+        let ray = ray_towards(Vec3::new(0.5, 0.2, -0.8));
+        let hit_record = hit_record(&ray, &mirror_and_glass);
+
+        // Half the samples come from a mirror reflecting 80% of the light, and half from glass that loses none.
+        let total = (0..SAMPLES)
+            .map(|_| {
+                let sample = mirror_and_glass.sample(&ray, &hit_record).unwrap();
+                assert!(sample.is_delta);
+                sample.weight
+            })
+            .fold(Colour::new(0.0, 0.0, 0.0), |total, weight| total + weight);
+        let mean = total / SAMPLES as Real;
+
+        assert!(mean.x() <= 1.0, "{mean:?}");
+        assert!((mean.x() - 0.9).abs() < 0.01, "{mean:?}");
+    }
+}
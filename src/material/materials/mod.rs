@@ -1,9 +1,29 @@
+mod alpha_mask;
+mod bump_map;
 mod checkerboard;
+mod conductor;
 mod dialectric;
+mod diffuse_light;
 mod lambertian;
+mod layered;
 mod metal;
+mod mix;
+mod normal_map;
+mod principled;
+mod rough_dielectric;
+mod subsurface;
 
+pub use alpha_mask::AlphaMask;
+pub use bump_map::BumpMap;
 pub use checkerboard::Checkerboard;
-pub use dialectric::Dielectric;
+pub use conductor::Conductor;
+pub use dialectric::{Dielectric, Dispersion};
+pub use diffuse_light::DiffuseLight;
 pub use lambertian::Lambertian;
+pub use layered::Layered;
 pub use metal::Metal;
+pub use mix::Mix;
+pub use normal_map::NormalMap;
+pub use principled::Principled;
+pub use rough_dielectric::RoughDielectric;
+pub use subsurface::Subsurface;
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    material::{sample_material, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, Medium},
+    ray::Ray,
+    texture::ArcTexture,
+    Colour, Real, Vec3,
+};
+
+/// A material that perturbs the shading normal of another material using a tangent-space normal map.
+///
+/// The map's red, green and blue channels are the normal's components along the tangent, bitangent and surface normal,
+/// mapped from `-1..1` to `0..1` in the usual way, so a flat surface is `(0.5, 0.5, 1.0)`. Normal maps hold data
+/// rather than colours, so image files should be loaded with
+/// [ImageTexture::open_linear](crate::texture::textures::ImageTexture::open_linear).
+pub struct NormalMap {
+    pub base: ArcMaterial,
+    pub map: ArcTexture,
+    /// How much to scale the tangential part of the normal by. 1 uses the map as is and 0 leaves the surface flat.
+    pub strength: Real,
+}
+
+impl NormalMap {
+    pub fn new(base: impl Into<ArcMaterial>, map: impl Into<ArcTexture>, strength: Real) -> Self {
+        Self {
+            base: base.into(),
+            map: map.into(),
+            strength,
+        }
+    }
+}
+
+impl Into<ArcMaterial> for NormalMap {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl NormalMap {
+    /// Copy a hit record, replacing its shading normal with the one from the map.
+    fn perturb<'a>(&self, ray: &Ray, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let texel = self.map.value_at(hit_record);
+        let (x, y, z) = (
+            texel.x() * 2.0 - 1.0,
+            texel.y() * 2.0 - 1.0,
+            texel.z() * 2.0 - 1.0,
+        );
+
+        // The tangent frame follows the outside of the surface, but the normal is flipped on the back, so the
+        // tangential part is flipped with it to keep the bumps in the same place from both sides.
+        let side = if hit_record.front_face { 1.0 } else { -1.0 };
+        let normal = (hit_record.tangent * x + hit_record.bitangent * y) * (self.strength * side)
+            + hit_record.normal * z;
+
+        let mut hit_record = hit_record.clone();
+        hit_record.set_shading_normal(ray, normal);
+        hit_record
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        self.base.scatter(ray, &self.perturb(ray, hit_record))
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Colour {
+        self.base.emitted(ray, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn medium(&self, hit_record: &HitRecord) -> Option<Medium> {
+        self.base.medium(hit_record)
+    }
+
+    fn has_cut_outs(&self) -> bool {
+        self.base.has_cut_outs()
+    }
+
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.base.is_cut_out(hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        self.base.bsdf().map(|_| self as &dyn Bsdf)
+    }
+}
+
+impl Bsdf for NormalMap {
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        sample_material(self.base.as_ref(), ray, &self.perturb(ray, hit_record))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.eval(ray, &self.perturb(ray, hit_record), direction),
+            None => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.pdf(ray, &self.perturb(ray, hit_record), direction),
+            None => 0.0,
+        }
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        match self.base.bsdf() {
+            Some(bsdf) => bsdf.is_delta(hit_record),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::materials::Lambertian, texture::textures::SolidColour, Point3};
+
+    #[test]
+    fn back_faces_are_perturbed_the_same_way() {
+        let grey = Lambertian::new(Colour::new(0.5, 0.5, 0.5));
+        let normal_map = NormalMap::new(grey, SolidColour::new(Colour::new(0.8, 0.4, 0.9)), 1.0);
+
+        // Hit the same point of a flat surface from either side.
+        let hit = |direction: Real| {
+            let ray = Ray::new(
+                Point3::new(0.0, 0.0, -direction),
+                Vec3::new(0.0, 0.0, direction),
+            );
+            let hit_record = HitRecord::new(
+                Point3::new(0.0, 0.0, 0.0),
+                1.0,
+                &ray,
+                Vec3::new(0.0, 0.0, 1.0),
+                (0.5, 0.5),
+                Vec3::new(1.0, 0.0, 0.0),
+                &normal_map,
+            );
+            normal_map.perturb(&ray, &hit_record).normal
+        };
+
+        let (front, back) = (hit(-1.0), hit(1.0));
+        assert!(
+            front.x() > 0.0 && front.y() < 0.0 && front.z() > 0.0,
+            "{front:?}"
+        );
+        assert!((front + back).length() < 1e-6, "{front:?} {back:?}");
+    }
+}
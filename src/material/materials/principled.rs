@@ -0,0 +1,451 @@
+use std::sync::Arc;
+
+use num_traits::FloatConst;
+use rand::Rng;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        fresnel::fresnel_dielectric,
+        microfacet::{reflect, refract, Frame, Ggx},
+        scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Channel, Material, MaterialRecord, Parameter,
+    },
+    ray::Ray,
+    texture::ArcTexture,
+    Colour, Real, Vec3,
+};
+
+/// The smallest GGX `alpha` the material uses. Every lobe stays rough enough to be evaluated, so lights can always be
+/// sampled directly, but it's too sharp to tell apart from a perfect mirror.
+const MIN_ALPHA: Real = 2.0 * Ggx::SMOOTH_ALPHA;
+
+/// The index of refraction of the clearcoat layer, which gives it the 4% reflectance glTF uses.
+const CLEARCOAT_IOR: Real = 1.5;
+
+/// The smallest sheen roughness, since the sheen distribution breaks down below it.
+const MIN_SHEEN_ROUGHNESS: Real = 0.07;
+
+/// A principled material, in the style of Disney's and glTF's, that covers most real materials with a handful of
+/// artist friendly parameters.
+///
+/// The material is a mix of a metal and a dielectric base, with an optional sheen and clearcoat on top:
+///
+/// - The metal's reflectance at normal incidence is `base_colour`.
+/// - The dielectric is a diffuse surface with a `base_colour` albedo, or a `base_colour` tinted rough glass where
+///   `transmission` is 1, under a specular layer whose strength is scaled by `specular`.
+/// - `sheen_colour` adds the soft glow at grazing angles that cloth has, and `clearcoat` adds a thin smooth varnish.
+///
+/// The parameters line up with glTF's metallic-roughness model and its extensions, so a glTF material maps onto it
+/// directly:
+///
+/// | glTF                                            | Field                              |
+/// |-------------------------------------------------|------------------------------------|
+/// | `baseColorFactor`, `baseColorTexture`           | `base_colour`                      |
+/// | `metallicFactor`, `metallicRoughnessTexture.b`  | `metallic`                         |
+/// | `roughnessFactor`, `metallicRoughnessTexture.g` | `roughness`                        |
+/// | `KHR_materials_specular.specularFactor`         | `specular`                         |
+/// | `KHR_materials_ior.ior`                         | `index_of_refraction`              |
+/// | `KHR_materials_transmission.transmissionFactor` | `transmission`                     |
+/// | `KHR_materials_clearcoat`                       | `clearcoat`, `clearcoat_roughness` |
+/// | `KHR_materials_sheen`                           | `sheen_colour`, `sheen_roughness`  |
+///
+/// Sheen is added on top of the base without darkening it, which is close enough for the small amounts of sheen cloth
+/// needs. Roughness is never quite zero, so even mirror-like surfaces have lights sampled directly.
+pub struct Principled {
+    pub base_colour: ArcTexture,
+    pub metallic: Parameter,
+    /// The perceptual roughness of the base, where 0 is perfectly smooth and 1 is very rough.
+    pub roughness: Parameter,
+    /// How strong the dielectric's specular reflection is, where 1 is physically correct.
+    pub specular: Parameter,
+    pub index_of_refraction: Real,
+    pub transmission: Parameter,
+    pub clearcoat: Parameter,
+    pub clearcoat_roughness: Parameter,
+    pub sheen_colour: ArcTexture,
+    pub sheen_roughness: Parameter,
+}
+
+/// The parameters of a [Principled] material at a hit, and how likely each lobe is to be sampled.
+struct Lobes {
+    frame: Frame,
+    wo: Vec3<Real>,
+    /// The index of refraction on the far side of the surface divided by the one on the near side.
+    eta: Real,
+    base_colour: Colour,
+    metallic: Real,
+    specular: Real,
+    transmission: Real,
+    clearcoat: Real,
+    sheen_colour: Colour,
+    sheen_alpha: Real,
+    ggx: Ggx,
+    clearcoat_ggx: Ggx,
+    /// How much light makes it through the clearcoat to the layers below.
+    base_weight: Real,
+    /// The probabilities of sampling the diffuse, specular, transmission, sheen and clearcoat lobes.
+    probabilities: [Real; 5],
+}
+
+/// Schlick's approximation of the Fresnel equations, which is how glTF defines the reflectance of metals.
+fn schlick(f0: Colour, cos_theta: Real) -> Colour {
+    let t = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Colour::new(1.0, 1.0, 1.0) - f0) * t
+}
+
+/// A GGX distribution from a perceptual roughness, keeping it rough enough to evaluate.
+fn ggx(roughness: Real) -> Ggx {
+    let alpha = roughness.clamp(0.0, 1.0).powi(2).max(MIN_ALPHA);
+
+    Ggx {
+        alpha_x: alpha,
+        alpha_y: alpha,
+    }
+}
+
+/// Pick a cosine weighted direction in the local frame.
+fn sample_cosine() -> Vec3<Real> {
+    let direction = Vec3::new(0.0, 0.0, 1.0) + Vec3::random_unit_vector();
+
+    if direction.length_squared() < 1e-12 {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        direction.unit_vector()
+    }
+}
+
+impl Principled {
+    /// Create a rough plastic-like material, which can be changed by setting the other fields.
+    pub fn new(base_colour: impl Into<ArcTexture>) -> Self {
+        Self {
+            base_colour: base_colour.into(),
+            metallic: 0.0.into(),
+            roughness: 0.5.into(),
+            specular: 1.0.into(),
+            index_of_refraction: 1.5,
+            transmission: 0.0.into(),
+            clearcoat: 0.0.into(),
+            clearcoat_roughness: 0.0.into(),
+            sheen_colour: Colour::new(0.0, 0.0, 0.0).into(),
+            sheen_roughness: 0.0.into(),
+        }
+    }
+
+    /// Create a material from glTF's core metallic-roughness parameters.
+    ///
+    /// The metallic-roughness texture holds roughness in its green channel and metallic in its blue channel, and is
+    /// multiplied by the factors like in glTF. It holds data rather than colours, so image files should be loaded with
+    /// [ImageTexture::open_linear](crate::texture::textures::ImageTexture::open_linear).
+    pub fn from_gltf(
+        base_colour: impl Into<ArcTexture>,
+        metallic_factor: Real,
+        roughness_factor: Real,
+        metallic_roughness: Option<ArcTexture>,
+    ) -> Self {
+        let (metallic, roughness) = match metallic_roughness {
+            Some(texture) => (
+                Parameter::Texture {
+                    texture: texture.clone(),
+                    channel: Channel::Blue,
+                    factor: metallic_factor,
+                },
+                Parameter::Texture {
+                    texture,
+                    channel: Channel::Green,
+                    factor: roughness_factor,
+                },
+            ),
+            None => (metallic_factor.into(), roughness_factor.into()),
+        };
+
+        Self {
+            metallic,
+            roughness,
+            ..Self::new(base_colour)
+        }
+    }
+
+    fn lobes(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Lobes> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let eta = if hit_record.front_face {
+            self.index_of_refraction
+        } else {
+            1.0 / self.index_of_refraction
+        };
+
+        let base_colour = self.base_colour.value_at(hit_record);
+        let metallic = self.metallic.value(hit_record).clamp(0.0, 1.0);
+        let specular = self.specular.value(hit_record).clamp(0.0, 1.0);
+        let transmission = self.transmission.value(hit_record).clamp(0.0, 1.0);
+        let clearcoat = self.clearcoat.value(hit_record).clamp(0.0, 1.0);
+        let sheen_colour = self.sheen_colour.value_at(hit_record);
+        let sheen_roughness = self
+            .sheen_roughness
+            .value(hit_record)
+            .clamp(MIN_SHEEN_ROUGHNESS, 1.0);
+
+        // Weight each lobe by roughly how much light it reflects towards the viewer.
+        let base_weight = 1.0 - clearcoat * fresnel_dielectric(wo.z(), CLEARCOAT_IOR);
+        let dielectric = 1.0 - metallic;
+        let dielectric_specular = specular * fresnel_dielectric(wo.z(), eta);
+        let base_luminance = base_colour.luminance().max(0.0);
+
+        let weights = [
+            base_weight
+                * dielectric
+                * (1.0 - transmission)
+                * (1.0 - dielectric_specular)
+                * base_luminance,
+            base_weight
+                * (dielectric * dielectric_specular
+                    + metallic * schlick(base_colour, wo.z()).luminance()),
+            base_weight * dielectric * transmission * (1.0 - dielectric_specular) * base_luminance,
+            base_weight * dielectric * sheen_colour.luminance().max(0.0),
+            clearcoat * fresnel_dielectric(wo.z(), CLEARCOAT_IOR),
+        ];
+
+        let total: Real = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        Some(Lobes {
+            frame,
+            wo,
+            eta,
+            base_colour,
+            metallic,
+            specular,
+            transmission,
+            clearcoat,
+            sheen_colour,
+            sheen_alpha: sheen_roughness * sheen_roughness,
+            ggx: ggx(self.roughness.value(hit_record)),
+            clearcoat_ggx: ggx(self.clearcoat_roughness.value(hit_record)),
+            base_weight,
+            probabilities: weights.map(|weight| weight / total),
+        })
+    }
+}
+
+impl Lobes {
+    /// The Charlie sheen distribution from "Production Friendly Microfacet Sheen BRDF" (Estevez & Kulla 2017).
+    fn sheen_d(&self, m: &Vec3<Real>) -> Real {
+        let inverse_alpha = 1.0 / self.sheen_alpha;
+        let sin_theta = (1.0 - m.z() * m.z()).max(0.0).sqrt();
+
+        (2.0 + inverse_alpha) * sin_theta.powf(inverse_alpha) / (2.0 * Real::PI())
+    }
+
+    /// The BSDF times the cosine, and the density of sampling `wi`, both in the local frame.
+    fn eval_pdf(&self, wi: &Vec3<Real>) -> (Colour, Real) {
+        let black = Colour::new(0.0, 0.0, 0.0);
+        let wo = self.wo;
+        let [p_diffuse, p_specular, p_transmission, p_sheen, p_clearcoat] = self.probabilities;
+        let dielectric = 1.0 - self.metallic;
+
+        if wi.z() == 0.0 {
+            return (black, 0.0);
+        }
+
+        if wi.z() > 0.0 {
+            let m = (wo + *wi).unit_vector();
+            let cos_om = wo.dot(&m);
+
+            let diffuse = self.base_colour
+                * (dielectric
+                    * (1.0 - self.transmission)
+                    * (1.0 - self.specular * fresnel_dielectric(wo.z(), self.eta))
+                    * wi.z()
+                    / Real::PI());
+
+            let fresnel = Colour::new(1.0, 1.0, 1.0)
+                * (dielectric * self.specular * fresnel_dielectric(cos_om, self.eta))
+                + schlick(self.base_colour, cos_om) * self.metallic;
+            let specular = fresnel * (self.ggx.d(&m) * self.ggx.g(&wo, wi) / (4.0 * wo.z()));
+
+            let visibility = 1.0 / (4.0 * (wi.z() + wo.z() - wi.z() * wo.z()));
+            let sheen = self.sheen_colour * (dielectric * self.sheen_d(&m) * visibility * wi.z());
+
+            let clearcoat = self.clearcoat
+                * fresnel_dielectric(cos_om, CLEARCOAT_IOR)
+                * self.clearcoat_ggx.d(&m)
+                * self.clearcoat_ggx.g(&wo, wi)
+                / (4.0 * wo.z());
+
+            let value = (diffuse + specular + sheen) * self.base_weight
+                + Colour::new(1.0, 1.0, 1.0) * clearcoat;
+
+            let cosine_pdf = wi.z() / Real::PI();
+            let pdf = (p_diffuse + p_sheen) * cosine_pdf
+                + p_specular * self.ggx.visible_d(&wo, &m) / (4.0 * cos_om)
+                + p_clearcoat * self.clearcoat_ggx.visible_d(&wo, &m) / (4.0 * cos_om);
+
+            return (value, pdf);
+        }
+
+        // The microfacet normal that refracts `wo` into `wi`, pointing out of the surface.
+        let mut m = (wo + *wi * self.eta).unit_vector();
+        if m.z() < 0.0 {
+            m = -m;
+        }
+
+        if wo.dot(&m) <= 0.0 || wi.dot(&m) >= 0.0 {
+            return (black, 0.0);
+        }
+
+        let transmitted = 1.0 - self.specular * fresnel_dielectric(wo.dot(&m), self.eta);
+        let denom = (wi.dot(&m) + wo.dot(&m) / self.eta).powi(2);
+        let dm_dwi = wi.dot(&m).abs() / denom;
+
+        let value = self.base_colour
+            * (self.base_weight
+                * dielectric
+                * self.transmission
+                * transmitted
+                * self.ggx.d(&m)
+                * self.ggx.g(&wo, wi)
+                * wo.dot(&m)
+                * dm_dwi
+                / wo.z());
+        let pdf = p_transmission * self.ggx.visible_d(&wo, &m) * dm_dwi;
+
+        (value, pdf)
+    }
+
+    /// Pick a lobe, then a direction from it.
+    fn sample_direction(&self) -> Option<Vec3<Real>> {
+        let [p_diffuse, p_specular, p_transmission, p_sheen, _] = self.probabilities;
+        let wo = self.wo;
+        let mut choice = rand::thread_rng().gen_range(0.0..1.0);
+
+        let wi = if choice < p_diffuse + p_sheen {
+            sample_cosine()
+        } else {
+            choice -= p_diffuse + p_sheen;
+
+            if choice < p_specular {
+                reflect(&wo, &self.ggx.sample_visible_normal(&wo))
+            } else if choice < p_specular + p_transmission {
+                let wi = refract(&wo, &self.ggx.sample_visible_normal(&wo), self.eta)?;
+                if wi.z() >= 0.0 {
+                    return None;
+                }
+                return Some(wi);
+            } else {
+                reflect(&wo, &self.clearcoat_ggx.sample_visible_normal(&wo))
+            }
+        };
+
+        if wi.z() <= 0.0 {
+            None
+        } else {
+            Some(wi)
+        }
+    }
+}
+
+impl Into<ArcMaterial> for Principled {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Principled {
+    /// Pick one of the lobes, weighted by how much light each one reflects, then weight the direction against the
+    /// whole material so the choice of lobe doesn't add noise.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let lobes = self.lobes(ray, hit_record)?;
+        let direction = lobes.frame.to_world(&lobes.sample_direction()?);
+
+        // Evaluate the direction the same way [Bsdf::eval] will, since the sharpest lobes change a lot with the
+        // rounding from going to world space and back.
+        let (value, pdf) = lobes.eval_pdf(&lobes.frame.to_local(&direction.unit_vector()));
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            direction,
+            weight: value / pdf,
+            pdf,
+            is_delta: false,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        match self.lobes(ray, hit_record) {
+            Some(lobes) => {
+                lobes
+                    .eval_pdf(&lobes.frame.to_local(&direction.unit_vector()))
+                    .0
+            }
+            None => Colour::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        match self.lobes(ray, hit_record) {
+            Some(lobes) => {
+                lobes
+                    .eval_pdf(&lobes.frame.to_local(&direction.unit_vector()))
+                    .1
+            }
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::bsdf::tests::{check_consistency, scattered_fraction},
+        Colour,
+    };
+
+    #[test]
+    fn sampling_matches_pdf() {
+        let mut plastic = Principled::new(Colour::new(0.8, 0.2, 0.1));
+        plastic.clearcoat = 1.0.into();
+        plastic.clearcoat_roughness = 0.1.into();
+        plastic.sheen_colour = Colour::new(0.5, 0.5, 0.5).into();
+        check_consistency(&plastic);
+
+        let mut glass = Principled::new(Colour::new(0.9, 0.9, 1.0));
+        glass.transmission = 1.0.into();
+        glass.roughness = 0.3.into();
+        check_consistency(&glass);
+
+        check_consistency(&Principled::from_gltf(
+            Colour::new(1.0, 0.8, 0.3),
+            1.0,
+            0.4,
+            None,
+        ));
+    }
+
+    #[test]
+    fn smooth_metal_reflects_its_base_colour() {
+        let base_colour = Colour::new(1.0, 0.8, 0.3);
+        let gold = Principled::from_gltf(base_colour, 1.0, 0.05, None);
+
+        let reflected = scattered_fraction(&gold, Vec3::new(0.0, 0.0, -1.0));
+        assert!((reflected - base_colour).length() < 0.02, "{reflected:?}");
+    }
+}
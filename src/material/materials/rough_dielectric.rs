@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        fresnel::fresnel_dielectric,
+        microfacet::{reflect, refract, Frame, Ggx},
+        scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord,
+    },
+    ray::Ray,
+    Colour, Real, Vec3,
+};
+
+/// Rough glass, using a GGX microfacet distribution for both reflection and refraction, from "Microfacet Models for
+/// Refraction through Rough Surfaces" (Walter et al. 2007).
+///
+/// With a roughness of 0 this behaves like [Dielectric](super::Dielectric), but with the exact Fresnel equations.
+/// Like [Dielectric](super::Dielectric), the light isn't scaled by the change in index of refraction as it passes
+/// through the surface, which cancels out for closed objects anyway.
+pub struct RoughDielectric {
+    pub index_of_refraction: Real,
+    /// The perceptual roughness, where 0 is perfectly smooth and 1 is very rough.
+    pub roughness: Real,
+}
+
+/// The parts of a hit that every method needs, in the local shading frame.
+struct Local {
+    frame: Frame,
+    wo: Vec3<Real>,
+    /// The index of refraction on the far side of the surface divided by the one on the near side.
+    eta: Real,
+    ggx: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: Real, roughness: Real) -> Self {
+        Self {
+            index_of_refraction,
+            roughness,
+        }
+    }
+
+    fn local(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Local> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let eta = if hit_record.front_face {
+            self.index_of_refraction
+        } else {
+            1.0 / self.index_of_refraction
+        };
+
+        Some(Local {
+            frame,
+            wo,
+            eta,
+            ggx: Ggx::from_roughness(self.roughness),
+        })
+    }
+
+    /// Evaluate the BSDF times the cosine, and the density of sampling `direction`.
+    fn eval_pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> (Real, Real) {
+        let Some(Local {
+            frame,
+            wo,
+            eta,
+            ggx,
+        }) = self.local(ray, hit_record)
+        else {
+            return (0.0, 0.0);
+        };
+
+        if ggx.is_smooth() {
+            return (0.0, 0.0);
+        }
+
+        let wi = frame.to_local(&direction.unit_vector());
+        if wi.z() == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        if wi.z() > 0.0 {
+            let m = (wo + wi).unit_vector();
+            let fresnel = fresnel_dielectric(wo.dot(&m), eta);
+
+            let value = fresnel * ggx.d(&m) * ggx.g(&wo, &wi) / (4.0 * wo.z());
+            let pdf = fresnel * ggx.visible_d(&wo, &m) / (4.0 * wo.dot(&m));
+
+            return (value, pdf);
+        }
+
+        // The microfacet normal that refracts `wo` into `wi`, pointing out of the surface.
+        let mut m = (wo + wi * eta).unit_vector();
+        if m.z() < 0.0 {
+            m = -m;
+        }
+
+        // Both directions have to be on the correct side of the microfacet.
+        if wo.dot(&m) <= 0.0 || wi.dot(&m) >= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let transmitted = 1.0 - fresnel_dielectric(wo.dot(&m), eta);
+        let denom = (wi.dot(&m) + wo.dot(&m) / eta).powi(2);
+        let dm_dwi = wi.dot(&m).abs() / denom;
+
+        let value = transmitted * ggx.d(&m) * ggx.g(&wo, &wi) * wo.dot(&m) * dm_dwi / wo.z();
+        let pdf = transmitted * ggx.visible_d(&wo, &m) * dm_dwi;
+
+        (value, pdf)
+    }
+}
+
+impl Into<ArcMaterial> for RoughDielectric {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for RoughDielectric {
+    /// Pick a visible microfacet, then reflect or refract through it with the Fresnel reflectance as the probability.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let Local {
+            frame,
+            wo,
+            eta,
+            ggx,
+        } = self.local(ray, hit_record)?;
+
+        let is_delta = ggx.is_smooth();
+        let m = if is_delta {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            ggx.sample_visible_normal(&wo)
+        };
+
+        let fresnel = fresnel_dielectric(wo.dot(&m), eta);
+        let reflected = rand::thread_rng().gen_range(0.0..1.0) < fresnel;
+
+        let wi = if reflected {
+            reflect(&wo, &m)
+        } else {
+            refract(&wo, &m, eta)?
+        };
+
+        // Rough microfacets can send the ray to the wrong side of the surface.
+        if reflected != (wi.z() > 0.0) {
+            return None;
+        }
+
+        // The Fresnel term cancels out with the choice between reflecting and refracting, and most of the rest
+        // cancels out with the density of visible normals.
+        let (weight, pdf) = if is_delta {
+            (1.0, 0.0)
+        } else {
+            let (value, pdf) = self.eval_pdf(ray, hit_record, &frame.to_world(&wi));
+            if pdf <= 0.0 {
+                return None;
+            }
+            (value / pdf, pdf)
+        };
+
+        Some(BsdfSample {
+            direction: frame.to_world(&wi),
+            weight: Colour::new(weight, weight, weight),
+            pdf,
+            is_delta,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        let (value, _) = self.eval_pdf(ray, hit_record, direction);
+        Colour::new(value, value, value)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        self.eval_pdf(ray, hit_record, direction).1
+    }
+
+    fn is_delta(&self, _: &HitRecord) -> bool {
+        Ggx::from_roughness(self.roughness).is_smooth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::bsdf::tests::{check_consistency, hit_record, ray_towards};
+
+    #[test]
+    fn sampling_matches_pdf() {
+        check_consistency(&RoughDielectric::new(1.5, 0.5));
+    }
+
+    #[test]
+    fn nearly_smooth_glass_reflects_the_fresnel_fraction() {
+        const SAMPLES: usize = 100_000;
+
+        let glass = RoughDielectric::new(1.5, 0.05);
+        let ray = ray_towards(Vec3::new(0.0, 0.0, -1.0));
+        let hit_record = hit_record(&ray, &glass);
+
+        // Glass reflects 4% of the light that hits it straight on, and lets the rest through.
+        let reflected = (0..SAMPLES)
+            .filter_map(|_| glass.sample(&ray, &hit_record))
+            .filter(|sample| sample.direction.z() > 0.0)
+            .count() as Real
+            / SAMPLES as Real;
+
+        assert!(
+            (reflected - fresnel_dielectric(1.0, 1.5)).abs() < 0.005,
+            "{reflected}"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use num_traits::FloatConst;
+use rand::Rng;
+
+use crate::{
+    hittable::HitRecord,
+    material::{
+        fresnel::fresnel_dielectric,
+        microfacet::{reflect, refract, Frame},
+        scatter_bsdf, ArcMaterial, Bsdf, BsdfSample, Material, MaterialRecord, Medium,
+    },
+    ray::Ray,
+    Colour, Real, Vec3,
+};
+
+/// A translucent material, like skin, marble, wax or milk, where light goes into the surface and scatters around
+/// before coming back out somewhere else.
+///
+/// Light that goes into the surface follows a random walk through the inside of the object until it gets back to the
+/// surface, so objects have to be closed. It leaves the surface in a random direction, which lets the renderer sample
+/// lights from there.
+pub struct Subsurface {
+    /// The colour of the surface once light has scattered around inside it, for objects that are much thicker than
+    /// the mean free path.
+    pub albedo: Colour,
+    /// The average distance light travels inside the object between scattering, for each colour channel. Skin has a
+    /// much longer mean free path for red than for blue, which is what makes it glow red when lit from behind.
+    pub mean_free_path: Colour,
+    pub index_of_refraction: Real,
+}
+
+impl Subsurface {
+    /// Create a subsurface material with an index of refraction of 1.4, which is about right for skin, wax and
+    /// marble.
+    pub fn new(albedo: Colour, mean_free_path: Colour) -> Self {
+        Self {
+            albedo,
+            mean_free_path,
+            index_of_refraction: 1.4,
+        }
+    }
+}
+
+/// The fraction of light a medium has to scatter each time light hits a particle for the surface of a thick object to
+/// have an albedo of `albedo`. This is the fit from "Practical and Controllable Subsurface Scattering for Production
+/// Path Tracing" (Chiang et al. 2016).
+fn single_scattering_albedo(albedo: Real) -> Real {
+    let albedo = albedo.clamp(0.0, 1.0);
+    let s = 4.09712 + 4.20863 * albedo
+        - (9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo).sqrt();
+
+    1.0 - s * s
+}
+
+impl Into<ArcMaterial> for Subsurface {
+    fn into(self) -> ArcMaterial {
+        Arc::new(self)
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialRecord> {
+        scatter_bsdf(self, ray, hit_record)
+    }
+
+    fn medium(&self, _: &HitRecord) -> Option<Medium> {
+        let Vec3(r, g, b) = self.mean_free_path;
+        let Vec3(ar, ag, ab) = self.albedo;
+        let extinction = |mean_free_path: Real| 1.0 / mean_free_path.max(1e-6);
+
+        Some(Medium {
+            extinction: Colour::new(extinction(r), extinction(g), extinction(b)),
+            albedo: Colour::new(
+                single_scattering_albedo(ar),
+                single_scattering_albedo(ag),
+                single_scattering_albedo(ab),
+            ),
+        })
+    }
+
+    fn bsdf(&self) -> Option<&dyn Bsdf> {
+        Some(self)
+    }
+}
+
+impl Bsdf for Subsurface {
+    /// Light arriving from outside is either reflected by the smooth surface or refracted into the object, picking
+    /// between them with the Fresnel reflectance. Light reaching the surface from inside leaves in a cosine weighted
+    /// direction.
+    fn sample(&self, ray: &Ray, hit_record: &HitRecord) -> Option<BsdfSample> {
+        let frame = Frame::new(hit_record);
+        let wo = frame.to_local(&-ray.direction.unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        if !hit_record.front_face {
+            let wi = -Vec3::new(0.0, 0.0, 1.0) - Vec3::random_unit_vector();
+            if wi.length_squared() < 1e-12 {
+                return None;
+            }
+
+            let wi = wi.unit_vector();
+            return Some(BsdfSample {
+                direction: frame.to_world(&wi),
+                weight: Colour::new(1.0, 1.0, 1.0),
+                pdf: -wi.z() / Real::PI(),
+                is_delta: false,
+            });
+        }
+
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let reflectance = fresnel_dielectric(wo.z(), self.index_of_refraction);
+        let wi = if rand::thread_rng().gen_range(0.0..1.0) < reflectance {
+            reflect(&wo, &normal)
+        } else {
+            refract(&wo, &normal, self.index_of_refraction)?
+        };
+
+        Some(BsdfSample {
+            direction: frame.to_world(&wi),
+            weight: Colour::new(1.0, 1.0, 1.0),
+            pdf: 0.0,
+            is_delta: true,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Colour {
+        Colour::new(1.0, 1.0, 1.0) * self.pdf(ray, hit_record, direction)
+    }
+
+    fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: &Vec3<Real>) -> Real {
+        if hit_record.front_face {
+            return 0.0;
+        }
+
+        // The shading normal faces into the object here, so light leaves through the other side.
+        let cos_theta = -direction.unit_vector().dot(&hit_record.normal);
+        cos_theta.max(0.0) / Real::PI()
+    }
+
+    fn is_delta(&self, hit_record: &HitRecord) -> bool {
+        hit_record.front_face
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point3;
+
+    #[test]
+    fn single_scattering_albedo_limits() {
+        assert!(single_scattering_albedo(0.0).abs() < 1e-4);
+        assert!((single_scattering_albedo(1.0) - 1.0).abs() < 1e-4);
+
+        // Thick objects only look as bright as their particles if they lose nothing, so the particles always have to
+        // be brighter than the surface in between.
+        for albedo in [0.1, 0.5, 0.9] {
+            let single = single_scattering_albedo(albedo);
+            assert!(albedo < single && single < 1.0, "{albedo} {single}");
+        }
+    }
+
+    #[test]
+    fn exit_pdf_integrates_to_one() {
+        const SAMPLES: usize = 100_000;
+
+        let skin = Subsurface::new(Colour::new(0.8, 0.5, 0.4), Colour::new(1.0, 0.5, 0.25));
+
+        // A ray reaching the surface from inside the object.
+        let ray = Ray::new(Point3::new(0.0, 0.0, -1.0), Vec3::new(0.2, -0.1, 1.0));
+        let hit_record = HitRecord::new(
+            Point3::new(0.2, -0.1, 0.0),
+            1.0,
+            &ray,
+            Vec3::new(0.0, 0.0, 1.0),
+            (0.5, 0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            &skin,
+        );
+        assert!(!hit_record.front_face);
+
+        // Integrate the density over every direction, and check the directions it picks agree with it.
+        let integral = (0..SAMPLES)
+            .map(|_| skin.pdf(&ray, &hit_record, &Vec3::random_unit_vector()) * 4.0 * Real::PI())
+            .sum::<Real>()
+            / SAMPLES as Real;
+        assert!((integral - 1.0).abs() < 0.02, "{integral}");
+
+        for _ in 0..1000 {
+            let sample = skin.sample(&ray, &hit_record).unwrap();
+            assert!(sample.direction.z() > 0.0);
+            let pdf = skin.pdf(&ray, &hit_record, &sample.direction);
+            assert!((sample.pdf - pdf).abs() <= 1e-4 * pdf);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! The building blocks of microfacet materials, which model rough surfaces as lots of tiny mirrors.
+//!
+//! Everything here works in a local shading frame, where the shading normal is `+z`. Use [Frame] to convert
+//! directions in and out of it.
+
+use num_traits::FloatConst;
+use rand::Rng;
+
+use crate::{hittable::HitRecord, Real, Vec3};
+
+/// An orthonormal frame around the shading normal of a hit.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub tangent: Vec3<Real>,
+    pub bitangent: Vec3<Real>,
+    pub normal: Vec3<Real>,
+}
+
+impl Frame {
+    /// Build a frame from the tangent, bitangent and shading normal of a hit.
+    pub fn new(hit_record: &HitRecord) -> Self {
+        Self {
+            tangent: hit_record.tangent,
+            bitangent: hit_record.bitangent,
+            normal: hit_record.normal,
+        }
+    }
+
+    /// Rotate the tangent and bitangent around the normal by `angle` radians, which turns the direction anisotropic
+    /// materials are stretched along.
+    pub fn rotated(&self, angle: Real) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            tangent: self.tangent * cos + self.bitangent * sin,
+            bitangent: self.bitangent * cos - self.tangent * sin,
+            normal: self.normal,
+        }
+    }
+
+    /// Convert a world space direction into the frame.
+    pub fn to_local(&self, v: &Vec3<Real>) -> Vec3<Real> {
+        Vec3::new(
+            v.dot(&self.tangent),
+            v.dot(&self.bitangent),
+            v.dot(&self.normal),
+        )
+    }
+
+    /// Convert a direction in the frame into world space.
+    pub fn to_world(&self, v: &Vec3<Real>) -> Vec3<Real> {
+        self.tangent * v.x() + self.bitangent * v.y() + self.normal * v.z()
+    }
+}
+
+/// Reflect `wo` about the microfacet normal `m`.
+pub fn reflect(wo: &Vec3<Real>, m: &Vec3<Real>) -> Vec3<Real> {
+    *m * (2.0 * wo.dot(m)) - *wo
+}
+
+/// Refract `wo` through a microfacet with normal `m`, where `eta` is the index of refraction on the far side divided
+/// by the one on `wo`'s side. Returns [None] for total internal reflection.
+pub fn refract(wo: &Vec3<Real>, m: &Vec3<Real>, eta: Real) -> Option<Vec3<Real>> {
+    let cos_theta_i = wo.dot(m);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i).max(0.0) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(-*wo / eta + *m * (cos_theta_i / eta - cos_theta_t))
+}
+
+/// The GGX (or Trowbridge-Reitz) distribution of microfacet normals, with Smith's height-correlated masking and
+/// shadowing.
+///
+/// The surface can be rougher along the tangent than along the bitangent, which is what makes brushed metal look
+/// brushed.
+#[derive(Clone, Copy, Debug)]
+pub struct Ggx {
+    pub alpha_x: Real,
+    pub alpha_y: Real,
+}
+
+impl Ggx {
+    /// Below this, the distribution is so narrow that it's better treated as a perfectly smooth surface.
+    pub const SMOOTH_ALPHA: Real = 1e-3;
+
+    /// Create an isotropic distribution from a perceptual roughness between 0 and 1, which is squared to get `alpha`
+    /// the same way as in glTF and most other tools.
+    pub fn from_roughness(roughness: Real) -> Self {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+
+        Self {
+            alpha_x: alpha,
+            alpha_y: alpha,
+        }
+    }
+
+    /// Create an anisotropic distribution from a perceptual roughness along the tangent and along the bitangent.
+    ///
+    /// If only one direction is smooth, it's kept just rough enough to evaluate, so the surface is still treated as
+    /// rough.
+    /// ```
+    /// # use magnetite::material::microfacet::Ggx;
+    /// let brushed = Ggx::anisotropic(0.6, 0.0);
+    /// assert!(!brushed.is_smooth());
+    /// assert!(brushed.alpha_x > brushed.alpha_y);
+    /// ```
+    pub fn anisotropic(roughness_x: Real, roughness_y: Real) -> Self {
+        let alpha = |roughness: Real| roughness.clamp(0.0, 1.0).powi(2);
+        let (alpha_x, alpha_y) = (alpha(roughness_x), alpha(roughness_y));
+
+        if alpha_x.max(alpha_y) < Self::SMOOTH_ALPHA {
+            return Self { alpha_x, alpha_y };
+        }
+
+        Self {
+            alpha_x: alpha_x.max(Self::SMOOTH_ALPHA),
+            alpha_y: alpha_y.max(Self::SMOOTH_ALPHA),
+        }
+    }
+
+    /// Whether the surface is smooth enough to be treated as a perfect mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < Self::SMOOTH_ALPHA
+    }
+
+    /// The density of microfacets with normal `m`.
+    pub fn d(&self, m: &Vec3<Real>) -> Real {
+        if m.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let x = m.x() / self.alpha_x;
+        let y = m.y() / self.alpha_y;
+        let t = x * x + y * y + m.z() * m.z();
+
+        1.0 / (Real::PI() * self.alpha_x * self.alpha_y * t * t)
+    }
+
+    /// Smith's auxiliary function, which measures how much of the surface is hidden when looking from `w`.
+    pub fn lambda(&self, w: &Vec3<Real>) -> Real {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta == 0.0 {
+            return Real::INFINITY;
+        }
+
+        let x = self.alpha_x * w.x();
+        let y = self.alpha_y * w.y();
+        let tan2_alpha2 = (x * x + y * y) / cos2_theta;
+
+        ((1.0 + tan2_alpha2).sqrt() - 1.0) / 2.0
+    }
+
+    /// The fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3<Real>) -> Real {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: &Vec3<Real>, wi: &Vec3<Real>) -> Real {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of microfacet normals that are visible from `wo`.
+    pub fn visible_d(&self, wo: &Vec3<Real>, m: &Vec3<Real>) -> Real {
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+
+        self.g1(wo) * wo.dot(m).max(0.0) * self.d(m) / wo.z()
+    }
+
+    /// Pick a microfacet normal that's visible from `wo`, with density [Ggx::visible_d].
+    ///
+    /// This uses the method from "Sampling the GGX Distribution of Visible Normals" (Heitz 2018), which never picks
+    /// normals facing away from `wo` so no samples are wasted.
+    pub fn sample_visible_normal(&self, wo: &Vec3<Real>) -> Vec3<Real> {
+        let mut rng = rand::thread_rng();
+
+        // Stretch the view direction so the distribution becomes a hemisphere.
+        let vh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+
+        let length_squared = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if length_squared > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / length_squared.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // Pick a point on the projected disk, squashing half of it to account for the hemisphere being tilted.
+        let r = rng.gen_range(0.0..1.0 as Real).sqrt();
+        let phi = rng.gen_range(0.0..2.0 * Real::PI());
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        // Unstretch the normal.
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Integrate a function over the sphere of directions by Monte Carlo.
+    fn integrate(f: impl Fn(&Vec3<Real>) -> Real) -> Real {
+        const SAMPLES: usize = 200_000;
+        let total: Real = (0..SAMPLES).map(|_| f(&Vec3::random_unit_vector())).sum();
+
+        total * 4.0 * Real::PI() / SAMPLES as Real
+    }
+
+    #[test]
+    fn distributions_are_normalised() {
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+
+        for ggx in [
+            Ggx::from_roughness(0.5),
+            Ggx::from_roughness(0.9),
+            Ggx {
+                alpha_x: 0.2,
+                alpha_y: 0.6,
+            },
+        ] {
+            // The projected area of the microfacets is the area of the surface.
+            let projected = integrate(|m| ggx.d(m) * m.z().max(0.0));
+            assert!((projected - 1.0).abs() < 0.05, "{projected}");
+
+            let visible = integrate(|m| ggx.visible_d(&wo, m));
+            assert!((visible - 1.0).abs() < 0.05, "{visible}");
+        }
+    }
+}
@@ -1,6 +1,16 @@
+mod bsdf;
+pub mod fresnel;
 mod material;
 mod material_record;
 pub mod materials;
+mod medium;
+pub mod microfacet;
+mod parameter;
+mod thin_film;
 
+pub use bsdf::{sample_material, scatter_bsdf, Bsdf, BsdfSample};
 pub use material::{ArcMaterial, Material};
 pub use material_record::MaterialRecord;
+pub use medium::{Medium, MediumSample};
+pub use parameter::{Channel, Parameter};
+pub use thin_film::ThinFilm;
@@ -0,0 +1,63 @@
+use crate::{hittable::HitRecord, texture::ArcTexture, Real};
+
+/// Which part of a texture's colour a [Parameter] reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luminance,
+}
+
+/// A scalar material parameter, like roughness, that's either the same everywhere or read from a texture.
+///
+/// Plain numbers can be used anywhere a parameter is expected.
+#[derive(Clone)]
+pub enum Parameter {
+    Constant(Real),
+    /// One channel of a texture, multiplied by `factor`.
+    Texture {
+        texture: ArcTexture,
+        channel: Channel,
+        factor: Real,
+    },
+}
+
+impl Parameter {
+    /// Read one channel of a texture.
+    pub fn texture(texture: impl Into<ArcTexture>, channel: Channel) -> Self {
+        Self::Texture {
+            texture: texture.into(),
+            channel,
+            factor: 1.0,
+        }
+    }
+
+    /// The value of the parameter at a hit.
+    pub fn value(&self, hit_record: &HitRecord) -> Real {
+        match self {
+            Parameter::Constant(value) => *value,
+            Parameter::Texture {
+                texture,
+                channel,
+                factor,
+            } => {
+                let colour = texture.value_at(hit_record);
+                let value = match channel {
+                    Channel::Red => colour.x(),
+                    Channel::Green => colour.y(),
+                    Channel::Blue => colour.z(),
+                    Channel::Luminance => colour.luminance(),
+                };
+
+                value * factor
+            }
+        }
+    }
+}
+
+impl Into<Parameter> for Real {
+    fn into(self) -> Parameter {
+        Parameter::Constant(self)
+    }
+}
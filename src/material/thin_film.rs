@@ -0,0 +1,63 @@
+use num::Complex;
+
+use crate::{material::fresnel::fresnel_thin_film, spectrum, Colour, Real};
+
+/// A thin transparent film on top of a surface, like soap, oil on water or the oxide layer on anodised metal.
+///
+/// Light reflected off the top of the film interferes with light reflected off the bottom, so some wavelengths are
+/// reflected much more than others and the surface shimmers with colours that change with the viewing angle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThinFilm {
+    /// The thickness of the film in nanometres. Films show the strongest colours between about 100nm and 1000nm.
+    pub thickness: Real,
+    pub index_of_refraction: Real,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: Real, index_of_refraction: Real) -> Self {
+        Self {
+            thickness,
+            index_of_refraction,
+        }
+    }
+
+    /// The fraction of light of each colour reflected by a surface with the film on it.
+    ///
+    /// `outside` is the index of refraction on the side the light comes from, and `inside` gives the index of
+    /// refraction of the surface under the film at each wavelength in nanometres. If the light has a single
+    /// `wavelength`, the reflectance is the same for every colour channel. Otherwise it's worked out over the visible
+    /// range and turned into RGB.
+    /// ```
+    /// # use magnetite::material::ThinFilm;
+    /// # use num::Complex;
+    /// let soap = ThinFilm::new(300.0, 1.33);
+    /// let colour = soap.reflectance(None, 1.0, 1.0, |_| Complex::new(1.0, 0.0));
+    ///
+    /// // The film picks out some colours more than others.
+    /// assert!((colour.x() - colour.y()).abs() > 0.01 || (colour.y() - colour.z()).abs() > 0.01);
+    /// ```
+    pub fn reflectance(
+        &self,
+        wavelength: Option<Real>,
+        cos_theta_i: Real,
+        outside: Real,
+        inside: impl Fn(Real) -> Complex<Real>,
+    ) -> Colour {
+        let reflectance = |wavelength: Real| {
+            fresnel_thin_film(
+                cos_theta_i,
+                self.index_of_refraction / outside,
+                self.thickness / wavelength,
+                inside(wavelength) / outside,
+            )
+        };
+
+        match wavelength {
+            Some(wavelength) => {
+                let reflectance = reflectance(wavelength);
+                Colour::new(reflectance, reflectance, reflectance)
+            }
+            None => spectrum::reflectance_to_rgb(reflectance),
+        }
+    }
+}
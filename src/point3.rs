@@ -1,17 +1,17 @@
-use crate::vec3::Vec3;
+use crate::{vec3::Vec3, Real};
 
-pub type Point3 = Vec3<f64>;
+pub type Point3 = Vec3<Real>;
 
 impl Point3 {
-    pub fn x(&self) -> f64 {
+    pub fn x(&self) -> Real {
         self.0
     }
 
-    pub fn y(&self) -> f64 {
+    pub fn y(&self) -> Real {
         self.1
     }
 
-    pub fn z(&self) -> f64 {
+    pub fn z(&self) -> Real {
         self.2
     }
 }
@@ -7,11 +7,11 @@ use crate::{
         materials::{Checkerboard, Dielectric, Lambertian, Metal},
         ArcMaterial,
     },
-    Colour, Point3, Vec3,
+    Colour, Point3, Real, Vec3,
 };
 use rand::{rngs::ThreadRng, Rng};
 
-fn random_f64(rng: &mut ThreadRng) -> f64 {
+fn random_f64(rng: &mut ThreadRng) -> Real {
     rng.gen_range(0.0..1.0)
 }
 
@@ -33,7 +33,7 @@ pub fn random_world() -> Vec<ArcHittable> {
     ];
 
     // Locations of the spheres so they can be avoided by the smaller ones.
-    let ball_locations = vec![
+    let ball_locations = [
         Point3::new(-4.0, 1.0, 0.0),
         Point3::new(0.0, 1.0, 0.0),
         Point3::new(4.0, 1.0, 0.0),
@@ -64,9 +64,9 @@ pub fn random_world() -> Vec<ArcHittable> {
             let radius = 0.1 + (random_f64(&mut rng) / 8.0);
 
             let center = Point3::new(
-                a as f64 + 0.9 * random_f64(&mut rng),
+                a as Real + 0.9 * random_f64(&mut rng),
                 radius,
-                b as f64 + 0.9 * random_f64(&mut rng),
+                b as Real + 0.9 * random_f64(&mut rng),
             );
 
             // Discard any spheres not on the disk.
@@ -1,16 +1,27 @@
-use crate::point3::Point3;
+use crate::{point3::Point3, Real};
 
 pub struct Ray {
     pub origin: Point3,
     pub direction: Point3,
+    /// The single wavelength of light the ray carries, in nanometres, once something like dispersion has split white
+    /// light up. Rays without one carry every wavelength.
+    pub wavelength: Option<Real>,
+    /// How fast the ray's footprint widens as it travels, as an angle in radians. Camera rays start out as wide as a
+    /// pixel so image textures can be filtered to match, and other rays have a spread of 0.
+    pub spread: Real,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Point3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            wavelength: None,
+            spread: 0.0,
+        }
     }
 
-    pub fn at(&self, t: f64) -> Point3 {
+    pub fn at(&self, t: Real) -> Point3 {
         self.origin + (self.direction * t)
     }
 }
@@ -0,0 +1,12 @@
+/// The floating point type used throughout the renderer, which is [f64] by default.
+///
+/// Enabling the `f32` feature switches the whole pipeline (rays, hit records, bounding volumes, primitives, materials
+/// and colours) to single precision, which halves the memory used by big scenes.
+#[cfg(not(feature = "f32"))]
+pub type Real = f64;
+
+/// The floating point type used throughout the renderer, which is [f32] because the `f32` feature is enabled.
+///
+/// This halves the memory used by big scenes. Turn the feature off to go back to [f64] everywhere.
+#[cfg(feature = "f32")]
+pub type Real = f32;
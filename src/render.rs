@@ -1,16 +1,22 @@
 use std::error::Error;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
 
 use indicatif::{ProgressBar, ProgressStyle};
 use rand::Rng;
 
+use crate::background::{backgrounds::Gradient, ArcBackground};
 use crate::camera::Camera;
 use crate::colour::Colour;
-use crate::hittable::{ArcHittable, BvhNode, Interval};
+use crate::hittable::{ArcHittable, BoundingBox, Bvh4, BvhLayout, BvhNode, HitRecord, Interval};
 
 use crate::image_encoder::ImageEncoder;
+use crate::light::{ArcLight, LightTree};
+use crate::material::{Bsdf, MaterialRecord, Medium, MediumSample};
 use crate::ray::Ray;
+use crate::spectrum::{self, SampledWavelengths};
+use crate::{Point3, Real, Vec3};
 
 #[derive(Debug, Clone)]
 pub struct RenderOptions {
@@ -20,32 +26,390 @@ pub struct RenderOptions {
     pub max_bounces: u32,
     pub show_progress_bar: bool,
     pub use_bvh: bool,
+    /// Use a 4-wide BVH tree with SIMD traversal instead of a binary one. Only used if `use_bvh` is true.
+    pub use_bvh4: bool,
+    /// A file to cache the shape of the BVH tree in between runs. Only used if `use_bvh` is true.
+    pub bvh_cache: Option<PathBuf>,
+    /// Trace each path with a few wavelengths of light instead of red, green and blue. Colours from materials and
+    /// lights are turned into spectra, so scenes look the same as in RGB, but dispersion is much less noisy.
+    pub spectral: bool,
+    /// The light arriving from infinitely far away, which is what rays that don't hit anything see. This is a pale blue
+    /// [Gradient] sky if it isn't set.
+    pub background: Option<ArcBackground>,
+    /// Lights without any geometry, like point lights and spotlights. These are lit directly with shadow rays, on top
+    /// of the objects that give off light.
+    pub lights: Vec<ArcLight>,
+}
+
+/// Convert a colour from a material or light into the colours a path carries, which are the spectrum at the path's
+/// wavelengths in spectral mode.
+fn path_colour(colour: Colour, wavelengths: Option<&SampledWavelengths>) -> Colour {
+    match wavelengths {
+        Some(wavelengths) => wavelengths.uplift(&colour),
+        None => colour,
+    }
+}
+
+/// Everything the renderer can sample directly as a light, which is the objects that give off light, the background
+/// and the lights without any geometry.
+struct Lights {
+    /// The objects that give off light, in a tree for picking the ones that light each point the most.
+    objects: LightTree,
+    /// Whether there are any objects that give off light.
+    has_objects: bool,
+    background: ArcBackground,
+    /// Lights that rays can't hit, so they're only ever sampled directly and don't take part in multiple importance
+    /// sampling.
+    punctual: Vec<ArcLight>,
+}
+
+impl Lights {
+    /// How often the background is sampled rather than the objects.
+    fn background_probability(&self) -> Real {
+        match (self.has_objects, self.background.can_sample()) {
+            (_, false) => 0.0,
+            (false, true) => 1.0,
+            (true, true) => 0.5,
+        }
+    }
+
+    /// Return true if there's anything to sample.
+    fn can_sample(&self) -> bool {
+        self.has_objects || self.background.can_sample()
+    }
+
+    /// Pick a random direction from `origin` towards one of the lights.
+    fn sample_direction(&self, origin: &Point3) -> Option<Vec3<Real>> {
+        if rand::thread_rng().gen_range(0.0..1.0) < self.background_probability() {
+            self.background.sample_direction()
+        } else {
+            self.objects.sample_direction(origin)
+        }
+    }
+
+    /// The probability density, with respect to solid angle, of [Lights::sample_direction] picking `direction` from
+    /// `origin`.
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3<Real>) -> Real {
+        let background_probability = self.background_probability();
+        let mut pdf = 0.0;
+
+        if background_probability < 1.0 {
+            pdf += (1.0 - background_probability) * self.objects.direction_pdf(origin, direction);
+        }
+        if background_probability > 0.0 {
+            pdf += background_probability * self.background.direction_pdf(direction);
+        }
+
+        pdf
+    }
+}
+
+/// Combine two sampling strategies using the power heuristic from "Optimally Combining Sampling Techniques for Monte
+/// Carlo Rendering" (Veach & Guibas 1995), returning the weight for the strategy with density `pdf`.
+fn power_heuristic(pdf: Real, other_pdf: Real) -> Real {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+/// Sample a direction towards one of the lights and return the light that arrives from it, weighted for multiple
+/// importance sampling against the material's own sampling.
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+fn sample_light(
+    ray: &Ray,
+    hit_record: &HitRecord,
+    bsdf: &dyn Bsdf,
+    world: &ArcHittable,
+    lights: &Lights,
+    wavelengths: Option<&SampledWavelengths>,
+) -> Colour {
+    let black = Colour::new(0.0, 0.0, 0.0);
+    let origin = hit_record.hit_location;
+
+    let Some(direction) = lights.sample_direction(&origin) else {
+        return black;
+    };
+
+    let light_pdf = lights.direction_pdf(&origin, &direction);
+    let scattering_pdf = bsdf.pdf(ray, hit_record, &direction);
+
+    // Written so a NaN density is thrown away too, rather than turning the whole pixel into NaN.
+    if !(light_pdf > 0.0) || scattering_pdf <= 0.0 {
+        return black;
+    }
+
+    // The shadow ray has to find the closest hit rather than just any hit, since that's what the light would be. If it
+    // doesn't hit anything, the light comes from the background.
+    let shadow_ray = hit_record.spawn_ray(direction);
+    let emitted = match world.hit(&shadow_ray, &Interval::new(0.0, Real::INFINITY)) {
+        Some(light_hit) => light_hit.material.emitted(&shadow_ray, &light_hit),
+        None => lights.background.colour(&direction),
+    };
+    let weight = power_heuristic(light_pdf, scattering_pdf);
+
+    path_colour(bsdf.eval(ray, hit_record, &direction), wavelengths)
+        * path_colour(emitted, wavelengths)
+        * (weight / light_pdf)
+}
+
+/// Pick one of the lights without any geometry and return the light it shines on the hit, if nothing's in the way.
+fn sample_punctual_light(
+    ray: &Ray,
+    hit_record: &HitRecord,
+    bsdf: &dyn Bsdf,
+    world: &ArcHittable,
+    lights: &Lights,
+    wavelengths: Option<&SampledWavelengths>,
+) -> Colour {
+    let black = Colour::new(0.0, 0.0, 0.0);
+    if lights.punctual.is_empty() {
+        return black;
+    }
+
+    let count = lights.punctual.len();
+    let light = &lights.punctual[rand::thread_rng().gen_range(0..count)];
+    let Some(sample) = light.sample(&hit_record.hit_location) else {
+        return black;
+    };
+
+    // Anything between the hit and the light casts a shadow.
+    let shadow_ray = hit_record.spawn_ray(sample.direction);
+    if world.occluded(&shadow_ray, &Interval::new(0.0, sample.distance)) {
+        return black;
+    }
+
+    path_colour(bsdf.eval(ray, hit_record, &sample.direction), wavelengths)
+        * path_colour(sample.irradiance, wavelengths)
+        * count as Real
+}
+
+/// The longest random walk a ray can take through a medium before it's treated as absorbed.
+const MAX_MEDIUM_STEPS: u32 = 256;
+
+/// The end of a random walk through a medium.
+struct Walk<'a> {
+    /// The last step of the walk, which reached `hit_record`.
+    ray: Ray,
+    hit_record: HitRecord<'a>,
+    weight: Colour,
+    /// Whether the ray scattered at all, rather than going straight through the medium.
+    scattered: bool,
+}
+
+/// Follow a ray through the medium inside an object, from particle to particle, until it gets back to the surface.
+/// Returns [None] if the ray was absorbed.
+fn random_walk<'a>(
+    ray: &Ray,
+    hit_record: HitRecord<'a>,
+    medium: &Medium,
+    world: &'a ArcHittable,
+) -> Option<Walk<'a>> {
+    let mut walk = Walk {
+        ray: Ray {
+            wavelength: ray.wavelength,
+            ..Ray::new(ray.origin, ray.direction)
+        },
+        hit_record,
+        weight: Colour::new(1.0, 1.0, 1.0),
+        scattered: false,
+    };
+
+    for _ in 0..MAX_MEDIUM_STEPS {
+        let length = walk.ray.direction.length();
+
+        match medium.sample(walk.hit_record.t * length) {
+            MediumSample::Surface { weight } => {
+                walk.weight *= weight;
+                return Some(walk);
+            }
+            MediumSample::Scatter { distance, weight } => {
+                walk.weight *= weight;
+                walk.scattered = true;
+                walk.ray = Ray {
+                    wavelength: walk.ray.wavelength,
+                    ..Ray::new(walk.ray.at(distance / length), Vec3::random_unit_vector())
+                };
+
+                // The particle is inside the object, so the ray should always hit the inside of it again. If it hits
+                // something else, the walk stops there.
+                walk.hit_record = world.hit(&walk.ray, &Interval::new(0.0, Real::INFINITY))?;
+                if walk.hit_record.front_face
+                    || walk.hit_record.material.medium(&walk.hit_record).is_none()
+                {
+                    return Some(walk);
+                }
+            }
+        }
+    }
+
+    None
 }
 
 /// Get the colour of a ray sent out into the world.
-fn ray_colour(ray: &Ray, world: &ArcHittable, max_depth: u32) -> Colour {
+///
+/// `scattering_pdf` is the density the previous material picked the ray's direction with, if the lights were also
+/// sampled directly from there. Any light the ray hits then gets weighted so it isn't counted twice.
+///
+/// In spectral mode, `wavelengths` are the wavelengths the path follows and the colour returned is the spectrum at
+/// each of them.
+fn ray_colour(
+    ray: &Ray,
+    world: &ArcHittable,
+    lights: &Lights,
+    scattering_pdf: Option<Real>,
+    max_depth: u32,
+    wavelengths: Option<&SampledWavelengths>,
+) -> Colour {
     // Return black if we've reached the maximum number of bounces.
-    if max_depth <= 0 {
+    if max_depth == 0 {
         return Colour::new(0.0, 0.0, 0.0);
     }
 
     // Check if the ray hits anything in the scene.
-    if let Some(hit_record) = world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
-        // If it does, check if the material scatters the ray or absorbs it.
-        if let Some(mat_record) = hit_record.material.scatter(ray, &hit_record) {
-            // Mix the color of the original ray with the color of the scattered ray.
-            return mat_record.attenuation
-                * ray_colour(&mat_record.scattered, world, max_depth - 1);
-        } else {
-            // Return black if the ray was absorbed.
+    if let Some(hit_record) = world.hit(ray, &Interval::new(0.0, Real::INFINITY)) {
+        // A ray that hits the back of a surface with a medium travelled through the inside of the object to get there,
+        // so it might have scattered on the way.
+        let medium = match hit_record.material.medium(&hit_record) {
+            Some(medium) if !hit_record.front_face => medium,
+            _ => {
+                return surface_colour(
+                    ray,
+                    &hit_record,
+                    world,
+                    lights,
+                    scattering_pdf,
+                    max_depth,
+                    wavelengths,
+                )
+            }
+        };
+
+        let medium = Medium {
+            extinction: path_colour(medium.extinction, wavelengths),
+            albedo: path_colour(medium.albedo, wavelengths),
+        };
+        let Some(walk) = random_walk(ray, hit_record, &medium, world) else {
             return Colour::new(0.0, 0.0, 0.0);
+        };
+
+        // Rays that scattered aren't going in the direction the last material picked any more.
+        let scattering_pdf = if walk.scattered { None } else { scattering_pdf };
+
+        return walk.weight
+            * surface_colour(
+                &walk.ray,
+                &walk.hit_record,
+                world,
+                lights,
+                scattering_pdf,
+                max_depth,
+                wavelengths,
+            );
+    }
+
+    // Rays that don't hit anything see the background, which might also have been sampled directly.
+    let background = path_colour(lights.background.colour(&ray.direction), wavelengths);
+    match scattering_pdf {
+        Some(pdf) => {
+            background * power_heuristic(pdf, lights.direction_pdf(&ray.origin, &ray.direction))
         }
+        None => background,
+    }
+}
+
+/// Get the colour of a ray that hit a surface, from the light the surface gives off and the light it scatters.
+fn surface_colour(
+    ray: &Ray,
+    hit_record: &HitRecord,
+    world: &ArcHittable,
+    lights: &Lights,
+    scattering_pdf: Option<Real>,
+    max_depth: u32,
+    wavelengths: Option<&SampledWavelengths>,
+) -> Colour {
+    // Start with any light the material gives off itself.
+    let mut emitted = path_colour(hit_record.material.emitted(ray, hit_record), wavelengths);
+    if let Some(pdf) = scattering_pdf {
+        emitted *= power_heuristic(pdf, lights.direction_pdf(&ray.origin, &ray.direction));
     }
 
-    // Calculate the world colour if the ray doesn't hit anything.
-    let unit_direction = ray.direction.unit_vector();
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (Colour::new(0.9, 0.9, 0.9) * (1.0 - t)) + (Colour::new(0.5, 0.7, 1.0) * t)
+    // Materials that split light up by wavelength need the ray to carry a single wavelength, so pick one if it
+    // doesn't have one yet. The rest of the path then follows that wavelength. In spectral mode that's the hero
+    // wavelength, and the others are dropped.
+    let split_ray;
+    let (ray, wavelength_weight) =
+        if ray.wavelength.is_none() && hit_record.material.is_dispersive() {
+            let (wavelength, weight) = match wavelengths {
+                Some(wavelengths) => (wavelengths.hero(), Colour::new(3.0, 0.0, 0.0)),
+                None => spectrum::sample_wavelength(),
+            };
+            split_ray = Ray {
+                wavelength: Some(wavelength),
+                ..Ray::new(ray.origin, ray.direction)
+            };
+            (&split_ray, weight)
+        } else {
+            (ray, Colour::new(1.0, 1.0, 1.0))
+        };
+
+    // Materials without a BSDF can only scatter the ray, so fall back to the old way of doing things.
+    let Some(bsdf) = hit_record.material.bsdf() else {
+        return match hit_record.material.scatter(ray, hit_record) {
+            Some(MaterialRecord {
+                attenuation,
+                mut scattered,
+            }) => {
+                scattered.wavelength = ray.wavelength;
+                emitted
+                    + wavelength_weight
+                        * path_colour(attenuation, wavelengths)
+                        * ray_colour(&scattered, world, lights, None, max_depth - 1, wavelengths)
+            }
+            None => emitted,
+        };
+    };
+
+    // Sample the lights directly, which is much less noisy for small lights. This is pointless for delta lobes,
+    // which can't scatter light from any direction other than the one they pick.
+    let sample_lights = lights.can_sample() && !bsdf.is_delta(hit_record);
+    let mut direct = if sample_lights {
+        sample_light(ray, hit_record, bsdf, world, lights, wavelengths)
+    } else {
+        Colour::new(0.0, 0.0, 0.0)
+    };
+    if !bsdf.is_delta(hit_record) {
+        direct += sample_punctual_light(ray, hit_record, bsdf, world, lights, wavelengths);
+    }
+
+    // Then check if the material scatters the ray or absorbs it.
+    let Some(sample) = bsdf.sample(ray, hit_record) else {
+        return emitted + wavelength_weight * direct;
+    };
+
+    let next_pdf = if sample_lights && !sample.is_delta {
+        Some(sample.pdf)
+    } else {
+        None
+    };
+
+    // Mix the color of the original ray with the color of the scattered ray.
+    let mut scattered = hit_record.spawn_ray(sample.direction);
+    scattered.wavelength = ray.wavelength;
+    emitted
+        + wavelength_weight
+            * (direct
+                + path_colour(sample.weight, wavelengths)
+                    * ray_colour(
+                        &scattered,
+                        world,
+                        lights,
+                        next_pdf,
+                        max_depth - 1,
+                        wavelengths,
+                    ))
 }
 
 /// Render a [Hittable] to a given image encoder using a camera.
@@ -61,14 +425,46 @@ pub fn render<O: ImageEncoder>(
         max_bounces,
         show_progress_bar,
         use_bvh,
+        use_bvh4,
+        bvh_cache,
+        spectral,
+        background,
+        lights,
     } = options;
 
     let mut image_buffer = O::new(image_width, image_height);
 
+    let objects: Vec<ArcHittable> = world.into();
+
+    // Keep track of the objects that give off light so they can be sampled directly, along with the background and the
+    // lights without any geometry.
+    let emissive: Vec<ArcHittable> = objects
+        .iter()
+        .filter(|object| object.is_emissive())
+        .cloned()
+        .collect();
+    let lights = Arc::new(Lights {
+        has_objects: !emissive.is_empty(),
+        objects: LightTree::new(emissive),
+        background: background.unwrap_or_else(|| Gradient::sky().into()),
+        punctual: lights,
+    });
+
     let world: ArcHittable = if use_bvh {
-        BvhNode::create(world.into()).into()
+        let boxes: Vec<BoundingBox> = objects.iter().map(|o| o.bounding_box()).collect();
+
+        let layout = match bvh_cache {
+            Some(path) => BvhLayout::load_or_build(path, &boxes),
+            None => BvhLayout::build(&boxes),
+        };
+
+        if use_bvh4 {
+            Bvh4::from_layout(objects, &layout).into()
+        } else {
+            BvhNode::from_layout(&objects, &layout).into()
+        }
     } else {
-        Arc::new(world.into())
+        Arc::new(objects)
     };
 
     let bar = if show_progress_bar {
@@ -84,12 +480,16 @@ pub fn render<O: ImageEncoder>(
         None
     };
 
+    // Every camera ray widens by a pixel's angle so textures can be filtered to the area it covers.
+    let spread = camera.pixel_spread(image_height);
+
     // Store all of the handles to the individual threads.
     let mut handles = Vec::with_capacity(image_height as usize);
 
     // For each row in the image, create a thread to calculate it's pixels.
     for j in 0..image_height {
         let world = world.clone();
+        let lights = lights.clone();
         let camera = camera.clone();
         let bar = bar.clone();
 
@@ -101,12 +501,29 @@ pub fn render<O: ImageEncoder>(
                 let mut colour = Colour::new(0.0, 0.0, 0.0);
 
                 for _ in 0..samples {
-                    let u = (f64::from(i) + rng.gen_range(0.0..1.0)) / f64::from(image_width - 1);
-                    let v = (f64::from(j) + rng.gen_range(0.0..1.0)) / f64::from(image_height - 1);
+                    let u = (i as Real + rng.gen_range(0.0..1.0)) / (image_width - 1) as Real;
+                    let v = (j as Real + rng.gen_range(0.0..1.0)) / (image_height - 1) as Real;
+
+                    let r = Ray {
+                        spread,
+                        ..camera.get_ray(u, v)
+                    };
 
-                    let r = camera.get_ray(u, v);
+                    // Spectral paths are gathered as XYZ colours, and only turned into RGB once the pixel's done.
+                    let sample = if spectral {
+                        let wavelengths = SampledWavelengths::sample();
+                        let spectrum =
+                            ray_colour(&r, &world, &lights, None, max_bounces, Some(&wavelengths));
+                        wavelengths.to_xyz(&spectrum)
+                    } else {
+                        ray_colour(&r, &world, &lights, None, max_bounces, None)
+                    };
 
-                    colour += ray_colour(&r, &world, max_bounces) * (1.0 / f64::from(samples))
+                    colour += sample * (1.0 / samples as Real)
+                }
+
+                if spectral {
+                    colour = spectrum::xyz_to_film_rgb(&colour);
                 }
 
                 if let Some(ref bar) = bar {
@@ -129,9 +546,62 @@ pub fn render<O: ImageEncoder>(
     for handle in handles {
         let (j, row) = handle.join().unwrap();
         for (i, colour) in row.iter().enumerate() {
-            image_buffer.set_pixel(i as u32, image_height - (j as u32 + 1), *colour)
+            image_buffer.set_pixel(i as u32, image_height - (j + 1), *colour)
         }
     }
 
     Ok(image_buffer)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::hittables::Sphere,
+        material::{materials::Subsurface, ArcMaterial},
+    };
+
+    /// The mean weight of random walks through a medium that absorbs nothing, starting from the middle of a sphere.
+    fn mean_walk_weight(extinction: Colour, walks: usize) -> Colour {
+        let wax: ArcMaterial =
+            Subsurface::new(Colour::new(1.0, 1.0, 1.0), Colour::new(0.5, 0.5, 0.5)).into();
+        let world: ArcHittable = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, &wax).into();
+        let medium = Medium {
+            extinction,
+            albedo: Colour::new(1.0, 1.0, 1.0),
+        };
+
+        let mut total = Colour::new(0.0, 0.0, 0.0);
+        for _ in 0..walks {
+            let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::random_unit_vector());
+            let hit_record = world
+                .hit(&ray, &Interval::new(0.0, Real::INFINITY))
+                .unwrap();
+
+            let walk = random_walk(&ray, hit_record, &medium, &world)
+                .expect("walks that lose nothing always get back to the surface");
+            assert!(!walk.hit_record.front_face);
+            assert!((walk.hit_record.hit_location.length() - 1.0).abs() < 1e-3);
+            total += walk.weight;
+        }
+
+        total / walks as Real
+    }
+
+    #[test]
+    fn random_walks_without_absorption_conserve_energy() {
+        // When every channel scatters the same, no light is lost on any walk.
+        let mean = mean_walk_weight(Colour::new(4.0, 4.0, 4.0), 2_000);
+        assert!(
+            (mean - Colour::new(1.0, 1.0, 1.0)).length() < 1e-6,
+            "{mean:?}"
+        );
+
+        // Otherwise walks are weighted differently for each channel, but nothing is lost on average.
+        let mean = mean_walk_weight(Colour::new(1.5, 2.0, 2.5), 20_000);
+        assert!(
+            (mean - Colour::new(1.0, 1.0, 1.0)).length() < 0.03,
+            "{mean:?}"
+        );
+    }
+}
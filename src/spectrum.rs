@@ -0,0 +1,367 @@
+//! Converting between wavelengths of light and colours, for effects like dispersion that depend on the wavelength and
+//! for spectral rendering.
+
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use crate::{Colour, Real, Vec3};
+
+/// The shortest wavelength of visible light, in nanometres.
+pub const MIN_WAVELENGTH: Real = 380.0;
+
+/// The longest wavelength of visible light, in nanometres.
+pub const MAX_WAVELENGTH: Real = 780.0;
+
+/// A piecewise Gaussian with a different width on each side of its peak.
+fn gaussian(x: Real, mean: Real, sigma_below: Real, sigma_above: Real) -> Real {
+    let sigma = if x < mean { sigma_below } else { sigma_above };
+    let t = (x - mean) / sigma;
+
+    (-0.5 * t * t).exp()
+}
+
+/// The CIE 1931 colour matching functions at a wavelength in nanometres, giving the XYZ colour of a single
+/// wavelength.
+///
+/// This uses the multi-lobe fit from "Simple Analytic Approximations to the CIE XYZ Color Matching Functions" (Wyman,
+/// Sloan & Shirley 2013), which is accurate to about 1%.
+/// ```
+/// # use magnetite::spectrum::cie_xyz;
+/// // People are most sensitive to green light.
+/// assert!((cie_xyz(555.0).y() - 1.0).abs() < 0.01);
+/// ```
+pub fn cie_xyz(wavelength: Real) -> Vec3<Real> {
+    let x = 1.056 * gaussian(wavelength, 599.8, 37.9, 31.0)
+        + 0.362 * gaussian(wavelength, 442.0, 16.0, 26.7)
+        - 0.065 * gaussian(wavelength, 501.1, 20.4, 26.2);
+    let y = 0.821 * gaussian(wavelength, 568.8, 46.9, 40.5)
+        + 0.286 * gaussian(wavelength, 530.9, 16.3, 31.1);
+    let z = 1.217 * gaussian(wavelength, 437.0, 11.8, 36.0)
+        + 0.681 * gaussian(wavelength, 459.0, 26.0, 13.8);
+
+    Vec3::new(x, y, z)
+}
+
+/// Convert a CIE XYZ colour to linear sRGB.
+// The matrix is written out as published, which is more precise than `f32` can hold.
+#[allow(clippy::excessive_precision)]
+pub fn xyz_to_rgb(xyz: &Vec3<Real>) -> Colour {
+    let Vec3(x, y, z) = *xyz;
+
+    Colour::new(
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    )
+}
+
+/// The linear sRGB colour of a single wavelength, with colours outside of sRGB clipped to it. This isn't normalised.
+fn wavelength_rgb(wavelength: Real) -> Colour {
+    let Vec3(r, g, b) = xyz_to_rgb(&cie_xyz(wavelength));
+
+    Colour::new(r.max(0.0), g.max(0.0), b.max(0.0))
+}
+
+/// The wavelengths, in nanometres, that split the visible range into blue, green and red bands for [uplift]. They're
+/// picked so that each band on its own looks as close as possible to the matching sRGB primary.
+const BAND_EDGES: [Real; 2] = [486.0, 589.0];
+
+/// Take the average of a function over the visible range.
+fn average(f: impl Fn(Real) -> Vec3<Real>) -> Vec3<Real> {
+    const STEPS: usize = 1000;
+    let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / STEPS as Real;
+
+    (0..STEPS)
+        .map(|i| f(MIN_WAVELENGTH + (i as Real + 0.5) * step))
+        .fold(Vec3::default(), |sum, value| sum + value)
+        / STEPS as Real
+}
+
+/// The average of [wavelength_rgb] over the visible range, which is what a spectrum with the same power at every
+/// wavelength looks like.
+fn white() -> Colour {
+    static WHITE: OnceLock<Colour> = OnceLock::new();
+
+    *WHITE.get_or_init(|| average(wavelength_rgb))
+}
+
+/// Pick a wavelength of visible light, in nanometres, for a path to follow.
+///
+/// Returns the wavelength along with the colour to weight the path by, which is scaled so that white light stays
+/// white on average.
+/// ```
+/// # use magnetite::spectrum::sample_wavelength;
+/// # use magnetite::Colour;
+/// let mut sum = Colour::new(0.0, 0.0, 0.0);
+/// for _ in 0..10_000 {
+///     sum += sample_wavelength().1 / 10_000.0;
+/// }
+///
+/// assert!((sum - Colour::new(1.0, 1.0, 1.0)).length() < 0.1);
+/// ```
+pub fn sample_wavelength() -> (Real, Colour) {
+    let wavelength = rand::thread_rng().gen_range(MIN_WAVELENGTH..MAX_WAVELENGTH);
+
+    (wavelength, wavelength_rgb(wavelength) / white())
+}
+
+/// The linear sRGB colour of a spectrum that has the same power at every wavelength, before it's scaled to white.
+fn film_white() -> Colour {
+    static WHITE: OnceLock<Colour> = OnceLock::new();
+
+    *WHITE.get_or_init(|| xyz_to_rgb(&average(cie_xyz)))
+}
+
+/// Convert the XYZ colour gathered by spectral rendering to linear sRGB.
+///
+/// A spectrum with the same power at every wavelength is treated as white, so it comes out as `(1, 1, 1)`, the same
+/// as white light does when rendering in RGB.
+/// ```
+/// # use magnetite::spectrum::{cie_xyz, xyz_to_film_rgb, MAX_WAVELENGTH, MIN_WAVELENGTH};
+/// # use magnetite::{Colour, Real, Vec3};
+/// let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+/// for i in 0..400 {
+///     xyz += cie_xyz(MIN_WAVELENGTH + i as Real + 0.5) * (MAX_WAVELENGTH - MIN_WAVELENGTH) / 400.0;
+/// }
+///
+/// assert!((xyz_to_film_rgb(&xyz) - Colour::new(1.0, 1.0, 1.0)).length() < 1e-3);
+/// ```
+pub fn xyz_to_film_rgb(xyz: &Vec3<Real>) -> Colour {
+    xyz_to_rgb(&(*xyz / (MAX_WAVELENGTH - MIN_WAVELENGTH))) / film_white()
+}
+
+/// The linear sRGB colour of a surface under white light, from how much light it reflects at each wavelength.
+///
+/// This only looks at the reflectance every 10nm, so it's quick enough to use while rendering for surfaces whose
+/// colour can't be given in RGB, like thin films.
+/// ```
+/// # use magnetite::spectrum::reflectance_to_rgb;
+/// # use magnetite::Colour;
+/// assert!((reflectance_to_rgb(|_| 0.5) - Colour::new(0.5, 0.5, 0.5)).length() < 1e-6);
+///
+/// let red = reflectance_to_rgb(|wavelength| if wavelength > 600.0 { 1.0 } else { 0.0 });
+/// assert!(red.x() > red.y() && red.x() > red.z());
+/// ```
+pub fn reflectance_to_rgb(reflectance: impl Fn(Real) -> Real) -> Colour {
+    const STEPS: usize = 40;
+    // Each wavelength and its XYZ colour, along with the colour of a perfect reflector.
+    type Table = ([(Real, Vec3<Real>); STEPS], Colour);
+    static TABLE: OnceLock<Table> = OnceLock::new();
+
+    let (table, white) = TABLE.get_or_init(|| {
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / STEPS as Real;
+        let table: [(Real, Vec3<Real>); STEPS] = std::array::from_fn(|i| {
+            let wavelength = MIN_WAVELENGTH + (i as Real + 0.5) * step;
+            (wavelength, cie_xyz(wavelength))
+        });
+        let white = xyz_to_rgb(
+            &table
+                .iter()
+                .fold(Vec3::default(), |sum, (_, xyz)| sum + *xyz),
+        );
+
+        (table, white)
+    });
+
+    let xyz = table
+        .iter()
+        .fold(Vec3::default(), |sum, (wavelength, xyz)| {
+            sum + *xyz * reflectance(*wavelength)
+        });
+
+    xyz_to_rgb(&xyz) / *white
+}
+
+/// The spectrum of each band used by [uplift], which is 1 inside the band and 0 everywhere else.
+fn band(wavelength: Real) -> Vec3<Real> {
+    let [blue_green, green_red] = BAND_EDGES;
+
+    if wavelength < blue_green {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else if wavelength < green_red {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+/// The matrix that turns an RGB colour into how bright each of the red, green and blue bands should be to look like
+/// it, as its three rows.
+fn band_weights() -> &'static [Vec3<Real>; 3] {
+    static WEIGHTS: OnceLock<[Vec3<Real>; 3]> = OnceLock::new();
+
+    WEIGHTS.get_or_init(|| {
+        // The colour each band looks like on its own, which are the columns of the matrix to invert.
+        let look = |channel: fn(&Vec3<Real>) -> Real| {
+            average(|wavelength| xyz_to_rgb(&cie_xyz(wavelength)) * channel(&band(wavelength)))
+                / film_white()
+        };
+        let (red, green, blue) = (look(Vec3::x), look(Vec3::y), look(Vec3::z));
+
+        let determinant = red.dot(&green.cross(&blue));
+        [
+            green.cross(&blue) / determinant,
+            blue.cross(&red) / determinant,
+            red.cross(&green) / determinant,
+        ]
+    })
+}
+
+/// The value at a wavelength of a spectrum that looks like an RGB colour, which is how colours are turned into spectra
+/// for spectral rendering.
+///
+/// The spectrum is made of flat red, green and blue bands, mixed so that it looks exactly like the original colour.
+/// White becomes 1 at every wavelength, and colours between 0 and 1 stay very close to that range, so they work as
+/// reflectances as well as for lights.
+///
+/// Each band isn't quite a pure primary, so light that bounces off several surfaces doesn't look exactly like the
+/// product of their RGB colours. For muted colours the difference stays under 0.01, but it grows with saturation and
+/// the number of bounces. Four bounces off a saturated blue like `(0.2, 0.3, 0.9)` end up about 0.07 away from the RGB
+/// product, mostly by losing blue.
+/// ```
+/// # use magnetite::spectrum::uplift;
+/// # use magnetite::Colour;
+/// let white = Colour::new(0.5, 0.5, 0.5);
+/// assert!((uplift(&white, 450.0) - 0.5).abs() < 1e-6);
+/// assert!((uplift(&white, 650.0) - 0.5).abs() < 1e-6);
+///
+/// let red = Colour::new(0.8, 0.1, 0.1);
+/// assert!(uplift(&red, 650.0) > uplift(&red, 450.0));
+/// ```
+pub fn uplift(colour: &Colour, wavelength: Real) -> Real {
+    let [red, green, blue] = band_weights();
+    let bands = Vec3::new(red.dot(colour), green.dot(colour), blue.dot(colour));
+
+    bands.dot(&band(wavelength))
+}
+
+/// The wavelengths a path follows in spectral rendering, in nanometres.
+///
+/// This is hero wavelength sampling, from "Hero Wavelength Spectral Sampling" (Wilkie et al. 2014). The first wavelength
+/// is the hero, which decides the path wherever it depends on the wavelength, and the other two are spread evenly
+/// across the visible range from it. Colours along a spectral path hold the spectrum at each of the wavelengths instead
+/// of red, green and blue.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampledWavelengths(pub [Real; 3]);
+
+impl SampledWavelengths {
+    /// Pick a hero wavelength uniformly from the visible range.
+    pub fn sample() -> Self {
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let hero = rand::thread_rng().gen_range(0.0..range);
+
+        Self([0.0, 1.0, 2.0].map(|i| MIN_WAVELENGTH + (hero + i * range / 3.0) % range))
+    }
+
+    pub fn hero(&self) -> Real {
+        self.0[0]
+    }
+
+    /// The spectrum of an RGB colour at each of the wavelengths. See [uplift].
+    pub fn uplift(&self, colour: &Colour) -> Colour {
+        let [a, b, c] = self.0.map(|wavelength| uplift(colour, wavelength));
+
+        Colour::new(a, b, c)
+    }
+
+    /// Estimate the XYZ colour of a spectrum from its value at each of the wavelengths. The average over many sets of
+    /// wavelengths converges to the spectrum's real XYZ colour, which [xyz_to_film_rgb] turns back into RGB.
+    pub fn to_xyz(&self, spectrum: &Colour) -> Vec3<Real> {
+        let values = [spectrum.x(), spectrum.y(), spectrum.z()];
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+
+        self.0
+            .iter()
+            .zip(values)
+            .fold(Vec3::default(), |sum, (wavelength, value)| {
+                sum + cie_xyz(*wavelength) * (value * range / 3.0)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spectral_colours_match_rgb() {
+        const SAMPLES: usize = 100_000;
+
+        for colour in [
+            Colour::new(1.0, 1.0, 1.0),
+            Colour::new(0.8, 0.2, 0.1),
+            Colour::new(0.1, 0.6, 0.3),
+            Colour::new(0.2, 0.3, 0.9),
+        ] {
+            let xyz = (0..SAMPLES)
+                .map(|_| {
+                    let wavelengths = SampledWavelengths::sample();
+                    wavelengths.to_xyz(&wavelengths.uplift(&colour))
+                })
+                .fold(Vec3::default(), |sum, xyz| sum + xyz)
+                / SAMPLES as Real;
+
+            let difference = xyz_to_film_rgb(&xyz) - colour;
+            assert!(difference.length() < 0.03, "{colour:?} {difference:?}");
+        }
+    }
+
+    #[test]
+    fn spectral_bounces_stay_close_to_rgb() {
+        // The colour of light that has bounced off every albedo in turn, worked out from the product of their spectra.
+        let spectral_product = |albedos: &[Colour]| {
+            let xyz = average(|wavelength| {
+                cie_xyz(wavelength)
+                    * albedos
+                        .iter()
+                        .map(|albedo| uplift(albedo, wavelength))
+                        .product::<Real>()
+            });
+
+            xyz_to_film_rgb(&(xyz * (MAX_WAVELENGTH - MIN_WAVELENGTH)))
+        };
+        let rgb_product = |albedos: &[Colour]| {
+            albedos
+                .iter()
+                .fold(Colour::new(1.0, 1.0, 1.0), |product, albedo| {
+                    product * *albedo
+                })
+        };
+
+        // Muted colours, like most real surfaces, stay within 0.01 of the RGB product.
+        for albedos in [
+            [
+                Colour::new(0.9, 0.9, 0.9),
+                Colour::new(0.8, 0.6, 0.4),
+                Colour::new(0.5, 0.7, 0.9),
+                Colour::new(0.7, 0.7, 0.7),
+            ],
+            [
+                Colour::new(0.6, 0.5, 0.4),
+                Colour::new(0.4, 0.5, 0.3),
+                Colour::new(0.5, 0.5, 0.5),
+                Colour::new(0.8, 0.7, 0.6),
+            ],
+        ] {
+            let difference = spectral_product(&albedos) - rgb_product(&albedos);
+            assert!(difference.length() < 0.01, "{albedos:?} {difference:?}");
+        }
+
+        // Saturated colours drift further, up to about 0.07 after four bounces off the same blue.
+        let (red, green, blue) = (
+            Colour::new(0.8, 0.2, 0.1),
+            Colour::new(0.1, 0.6, 0.3),
+            Colour::new(0.2, 0.3, 0.9),
+        );
+        for albedos in [
+            vec![red, green, blue],
+            vec![red; 3],
+            vec![green; 4],
+            vec![blue; 4],
+        ] {
+            let difference = spectral_product(&albedos) - rgb_product(&albedos);
+            assert!(difference.length() < 0.08, "{albedos:?} {difference:?}");
+        }
+    }
+}
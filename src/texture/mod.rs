@@ -0,0 +1,5 @@
+pub mod noise;
+mod texture;
+pub mod textures;
+
+pub use texture::{ArcTexture, Texture};
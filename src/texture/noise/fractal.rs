@@ -0,0 +1,63 @@
+use crate::{Point3, Real, Vec3};
+
+use super::Noise;
+
+/// Fractional Brownian motion: the sum of several octaves of noise, where each octave has `lacunarity` times the
+/// frequency and `gain` times the amplitude of the one before. The result is normalised to roughly the same range as
+/// the noise itself.
+pub fn fbm(noise: &impl Noise, p: &Point3, octaves: u32, lacunarity: Real, gain: Real) -> Real {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+    let mut p = *p;
+
+    for _ in 0..octaves {
+        sum += noise.noise(&p) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= gain;
+        p *= lacunarity;
+    }
+
+    if total_amplitude > 0.0 {
+        sum / total_amplitude
+    } else {
+        0.0
+    }
+}
+
+/// Like [fbm], but summing the absolute value of each octave. This creates sharp creases where the noise crosses
+/// zero, which is useful for marble veins and flames. The result is roughly between 0 and 1.
+pub fn turbulence(noise: &impl Noise, p: &Point3, octaves: u32) -> Real {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+    let mut p = *p;
+
+    for _ in 0..octaves {
+        sum += noise.noise(&p).abs() * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        p *= 2.0;
+    }
+
+    if total_amplitude > 0.0 {
+        sum / total_amplitude
+    } else {
+        0.0
+    }
+}
+
+/// Distort a point using noise, so that looking up another pattern at the returned point gives a swirly, organic
+/// version of it. Each axis is offset by up to `strength` using differently shifted lookups into the noise.
+pub fn domain_warp(noise: &impl Noise, p: &Point3, octaves: u32, strength: Real) -> Point3 {
+    // Arbitrary offsets, so the three axes aren't warped by the same amount.
+    let offsets = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(5.2, 1.3, 2.8),
+        Vec3::new(1.7, 9.2, 4.1),
+    ];
+
+    let warp = offsets.map(|offset| fbm(noise, &(*p + offset), octaves, 2.0, 0.5));
+
+    *p + Vec3::new(warp[0], warp[1], warp[2]) * strength
+}
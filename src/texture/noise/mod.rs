@@ -0,0 +1,113 @@
+//! Deterministic noise functions for building procedural textures.
+//!
+//! Every noise function is created from a seed, and gives the same results for the same seed on every machine and
+//! every run, so renders using them are reproducible.
+
+mod fractal;
+mod noise;
+mod perlin;
+mod simplex;
+mod worley;
+
+pub use fractal::{domain_warp, fbm, turbulence};
+pub use noise::Noise;
+pub use perlin::Perlin;
+pub use simplex::Simplex;
+pub use worley::Worley;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Point3, Real};
+
+    /// Points spread over a few hundred lattice cells, including negative coordinates.
+    fn points() -> impl Iterator<Item = Point3> {
+        (0..20_000).map(|i| {
+            let i = i as Real;
+            Point3::new(
+                (i * 0.618_034).fract() * 64.0 - 32.0,
+                (i * 0.754_878).fract() * 64.0 - 32.0,
+                (i * 0.569_840).fract() * 64.0 - 32.0,
+            )
+        })
+    }
+
+    /// Check that the same seed always gives the same noise, a different seed gives different noise, and the values
+    /// stay between -1 and 1 while using most of that range.
+    fn check<N: Noise>(new: impl Fn(u64) -> N, noise: impl Fn(&N, &Point3) -> Real) {
+        let (first, again, other) = (new(3), new(3), new(4));
+        let (mut min, mut max) = (Real::INFINITY, Real::NEG_INFINITY);
+        let mut differences = 0;
+
+        for p in points() {
+            let value = noise(&first, &p);
+            assert_eq!(value, noise(&again, &p));
+            if value != noise(&other, &p) {
+                differences += 1;
+            }
+
+            assert!((-1.0..=1.0).contains(&value), "{value} at {p:?}");
+            min = min.min(value);
+            max = max.max(value);
+        }
+
+        assert!(differences > 19_000, "{differences}");
+        assert!(min < -0.5 && max > 0.5, "{min} {max}");
+    }
+
+    #[test]
+    fn perlin() {
+        check(Perlin::new, Perlin::noise);
+
+        // Perlin noise is zero at every lattice point.
+        let perlin = Perlin::new(3);
+        assert_eq!(perlin.noise(&Point3::new(2.0, -7.0, 300.0)), 0.0);
+    }
+
+    #[test]
+    fn simplex() {
+        check(Simplex::new, Simplex::noise);
+    }
+
+    #[test]
+    fn worley() {
+        let (first, again, other) = (Worley::new(3), Worley::new(3), Worley::new(4));
+        let (mut min, mut max) = (Real::INFINITY, Real::NEG_INFINITY);
+        let mut differences = 0;
+
+        for p in points() {
+            let (f1, f2) = first.distances(&p);
+            assert_eq!(again.distances(&p), (f1, f2));
+            if other.distances(&p) != (f1, f2) {
+                differences += 1;
+            }
+
+            // Every cell has a feature point, so the closest one is never further away than the corners of the cell.
+            assert!(0.0 <= f1 && f1 <= f2, "{f1} {f2} at {p:?}");
+            assert!(f1 <= (3.0 as Real).sqrt(), "{f1} at {p:?}");
+
+            let value = first.noise(&p);
+            assert_eq!(value, f1 * 2.0 - 1.0);
+            min = min.min(value);
+            max = max.max(value);
+        }
+
+        // The noise is -1 right on a feature point, and F1 is rarely much more than 1, so it only roughly fits
+        // between -1 and 1.
+        assert!(differences > 19_000, "{differences}");
+        assert!(min < -0.9 && max > 0.5, "{min} {max}");
+    }
+
+    #[test]
+    fn fbm_octaves() {
+        check(Perlin::new, |perlin, p| fbm(perlin, p, 5, 2.0, 0.5));
+        check(Simplex::new, |simplex, p| fbm(simplex, p, 5, 2.0, 0.5));
+
+        // One octave is just the noise, and no octaves is nothing.
+        let perlin = Perlin::new(3);
+        for p in points().take(100) {
+            assert_eq!(fbm(&perlin, &p, 1, 2.0, 0.5), perlin.noise(&p));
+            assert_eq!(fbm(&perlin, &p, 0, 2.0, 0.5), 0.0);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Point3, Real};
+
+/// Trait for a function that gives a smoothly varying random value for every point in space.
+pub trait Noise {
+    /// Get the value of the noise at a point, roughly between -1 and 1.
+    fn noise(&self, p: &Point3) -> Real;
+}
+
+/// A tiny pseudo-random number generator (SplitMix64).
+///
+/// The noise functions use this rather than [rand] so that a seed always gives the same noise, no matter which version
+/// of [rand] is being used.
+pub(super) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(super) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        mix(self.0)
+    }
+}
+
+/// The finalising step of SplitMix64, which scrambles the bits of a number. Useful as a hash function.
+pub(super) fn mix(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Create a random permutation of the numbers 0 to 255 from a seed, repeated twice so it can be indexed with the sum
+/// of two entries without wrapping.
+pub(super) fn permutation(seed: u64) -> [u8; 512] {
+    let mut rng = SplitMix64::new(seed);
+    let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+    // Fisher-Yates shuffle.
+    for i in (1..256).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        table.swap(i, j);
+    }
+
+    std::array::from_fn(|i| table[i % 256])
+}
@@ -0,0 +1,86 @@
+use crate::{Point3, Real};
+
+use super::{noise::permutation, Noise};
+
+/// Ken Perlin's "improved" gradient noise.
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: permutation(seed),
+        }
+    }
+
+    /// The dot product of one of twelve gradient directions (chosen by the hash) with `(x, y, z)`.
+    fn gradient(hash: u8, x: Real, y: Real, z: Real) -> Real {
+        let h = hash & 15;
+        let u = if h < 8 { x } else { y };
+        let v = match h {
+            0..=3 => y,
+            12 | 14 => x,
+            _ => z,
+        };
+
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+}
+
+/// The quintic curve `6t^5 - 15t^4 + 10t^3`, which smooths the interpolation between lattice points.
+fn fade(t: Real) -> Real {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: Real, a: Real, b: Real) -> Real {
+    a + t * (b - a)
+}
+
+impl Noise for Perlin {
+    fn noise(&self, p: &Point3) -> Real {
+        let p_floor = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (x, y, z) = (p.x() - p_floor.0, p.y() - p_floor.1, p.z() - p_floor.2);
+
+        let xi = (p_floor.0 as i64).rem_euclid(256) as usize;
+        let yi = (p_floor.1 as i64).rem_euclid(256) as usize;
+        let zi = (p_floor.2 as i64).rem_euclid(256) as usize;
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.permutation;
+        let a = perm[xi] as usize + yi;
+        let aa = perm[a] as usize + zi;
+        let ab = perm[a + 1] as usize + zi;
+        let b = perm[xi + 1] as usize + yi;
+        let ba = perm[b] as usize + zi;
+        let bb = perm[b + 1] as usize + zi;
+
+        let grad = Self::gradient;
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(perm[aa], x, y, z), grad(perm[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(perm[ab], x, y - 1.0, z),
+                    grad(perm[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(perm[aa + 1], x, y, z - 1.0),
+                    grad(perm[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(perm[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(perm[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Point3, Real};
+
+use super::{noise::permutation, Noise};
+
+/// The twelve gradient directions used by simplex noise, pointing at the edges of a cube.
+const GRADIENTS: [[Real; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Ken Perlin's simplex noise, following Stefan Gustavson's "Simplex noise demystified".
+///
+/// Simplex noise has fewer directional artifacts than [Perlin](super::Perlin) noise, and is cheaper to evaluate
+/// because it only interpolates between the four corners of a tetrahedron rather than the eight corners of a cube.
+pub struct Simplex {
+    permutation: [u8; 512],
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: permutation(seed),
+        }
+    }
+
+    /// The contribution of one corner of the simplex, at an offset from the point being evaluated.
+    fn corner(&self, gradient: usize, x: Real, y: Real, z: Real) -> Real {
+        let t = 0.6 - x * x - y * y - z * z;
+        if t < 0.0 {
+            return 0.0;
+        }
+
+        let g = GRADIENTS[gradient % 12];
+        let t2 = t * t;
+        t2 * t2 * (g[0] * x + g[1] * y + g[2] * z)
+    }
+}
+
+impl Noise for Simplex {
+    fn noise(&self, p: &Point3) -> Real {
+        const SKEW: Real = 1.0 / 3.0;
+        const UNSKEW: Real = 1.0 / 6.0;
+
+        // Skew the input space to find which simplex cell the point is in.
+        let s = (p.x() + p.y() + p.z()) * SKEW;
+        let i = (p.x() + s).floor();
+        let j = (p.y() + s).floor();
+        let k = (p.z() + s).floor();
+
+        // Unskew the cell's origin back to the input space to find the offset from the first corner.
+        let t = (i + j + k) * UNSKEW;
+        let x0 = p.x() - (i - t);
+        let y0 = p.y() - (j - t);
+        let z0 = p.z() - (k - t);
+
+        // Find which of the six tetrahedra in the cube the point is in.
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 {
+                (1, 0, 0, 1, 1, 0)
+            } else if x0 >= z0 {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if y0 < z0 {
+            (0, 0, 1, 0, 1, 1)
+        } else if x0 < z0 {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+
+        // Offsets for the other three corners.
+        let x1 = x0 - i1 as Real + UNSKEW;
+        let y1 = y0 - j1 as Real + UNSKEW;
+        let z1 = z0 - k1 as Real + UNSKEW;
+        let x2 = x0 - i2 as Real + 2.0 * UNSKEW;
+        let y2 = y0 - j2 as Real + 2.0 * UNSKEW;
+        let z2 = z0 - k2 as Real + 2.0 * UNSKEW;
+        let x3 = x0 - 1.0 + 3.0 * UNSKEW;
+        let y3 = y0 - 1.0 + 3.0 * UNSKEW;
+        let z3 = z0 - 1.0 + 3.0 * UNSKEW;
+
+        // Hash each corner to pick its gradient.
+        let ii = (i as i64).rem_euclid(256) as usize;
+        let jj = (j as i64).rem_euclid(256) as usize;
+        let kk = (k as i64).rem_euclid(256) as usize;
+        let perm = &self.permutation;
+        let hash = |di: usize, dj: usize, dk: usize| -> usize {
+            perm[ii + di + perm[jj + dj + perm[kk + dk] as usize] as usize] as usize
+        };
+
+        let n0 = self.corner(hash(0, 0, 0), x0, y0, z0);
+        let n1 = self.corner(hash(i1, j1, k1), x1, y1, z1);
+        let n2 = self.corner(hash(i2, j2, k2), x2, y2, z2);
+        let n3 = self.corner(hash(1, 1, 1), x3, y3, z3);
+
+        // Scale the result to roughly fit between -1 and 1.
+        32.0 * (n0 + n1 + n2 + n3)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{Point3, Real};
+
+use super::{noise::mix, Noise};
+
+/// Steven Worley's cellular noise.
+///
+/// Space is split into a grid of unit cells with one randomly placed feature point in each. The noise is based on
+/// the distances from a point to its closest feature points, which gives a pattern of cells like cracked mud, stone or
+/// scales.
+pub struct Worley {
+    seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Get the feature point of a grid cell.
+    fn feature_point(&self, x: i64, y: i64, z: i64) -> Point3 {
+        let hash = mix(self.seed
+            ^ mix((x as u64).wrapping_mul(0x8da6b343)
+                ^ (y as u64).wrapping_mul(0xd8163841)
+                ^ (z as u64).wrapping_mul(0xcb1ab31f)));
+
+        // Use 21 bits of the hash for each axis.
+        let offset = |shift: u32| ((hash >> shift) & 0x1fffff) as Real / 0x200000 as Real;
+
+        Point3::new(
+            x as Real + offset(0),
+            y as Real + offset(21),
+            z as Real + offset(42),
+        )
+    }
+
+    /// Get the distances from a point to its closest (F1) and second closest (F2) feature points.
+    ///
+    /// Only the 27 cells around the point are searched. The closest feature point is always in one of them, but very
+    /// rarely the second closest is two cells away, which makes F2 a little too big at that point.
+    /// ```
+    /// # use magnetite::texture::noise::Worley;
+    /// # use magnetite::Point3;
+    /// let worley = Worley::new(7);
+    /// let (f1, f2) = worley.distances(&Point3::new(0.3, 2.5, -1.2));
+    /// assert!(f1 <= f2);
+    /// // The same seed always gives the same noise.
+    /// assert_eq!(Worley::new(7).distances(&Point3::new(0.3, 2.5, -1.2)), (f1, f2));
+    /// ```
+    pub fn distances(&self, p: &Point3) -> (Real, Real) {
+        let cell = (
+            p.x().floor() as i64,
+            p.y().floor() as i64,
+            p.z().floor() as i64,
+        );
+
+        let mut f1 = Real::INFINITY;
+        let mut f2 = Real::INFINITY;
+
+        // The closest feature point is always in one of the neighbouring cells, and the second closest almost always
+        // is too.
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let feature = self.feature_point(cell.0 + dx, cell.1 + dy, cell.2 + dz);
+                    let distance = (feature - *p).length_squared();
+
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+
+        (f1.sqrt(), f2.sqrt())
+    }
+}
+
+impl Noise for Worley {
+    /// The distance to the closest feature point, remapped to roughly fit between -1 and 1.
+    fn noise(&self, p: &Point3) -> Real {
+        let (f1, _) = self.distances(p);
+        f1 * 2.0 - 1.0
+    }
+}
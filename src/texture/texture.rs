@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use crate::{hittable::HitRecord, Colour, Point3, Real};
+
+/// Trait for anything that can colour a surface.
+///
+/// Textures are looked up using the `(u, v)` surface coordinates of a hit, which move with the object, as well as the
+/// hit's location in world space for textures that are defined in 3D.
+pub trait Texture {
+    fn value(&self, u: Real, v: Real, p: &Point3) -> Colour;
+
+    /// Look the texture up where a ray hit a surface.
+    ///
+    /// Materials should use this rather than [Texture::value], since the hit record also says how much of the surface
+    /// the ray covers, which textures made from images use to avoid aliasing. The default implementation just calls
+    /// [Texture::value] with the hit's surface coordinates and location.
+    fn value_at(&self, hit_record: &HitRecord) -> Colour {
+        self.value(hit_record.u, hit_record.v, &hit_record.hit_location)
+    }
+}
+
+/// A texture trait object that can be shared between threads.
+pub type ArcTexture = Arc<dyn Texture + Send + Sync>;
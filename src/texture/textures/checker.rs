@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    texture::{ArcTexture, Texture},
+    Colour, Point3, Real,
+};
+
+/// A checkerboard texture in surface coordinates that alternates between two other textures.
+///
+/// Unlike the [Checkerboard](crate::material::materials::Checkerboard) material, the pattern stays attached to the
+/// surface when an object moves.
+/// ```
+/// # use magnetite::texture::{textures::Checker, Texture};
+/// # use magnetite::{Colour, Point3};
+/// let white = Colour::new(1.0, 1.0, 1.0);
+/// let black = Colour::new(0.0, 0.0, 0.0);
+/// let checker = Checker::new(2.0, white, black);
+///
+/// let p = Point3::default();
+/// assert_eq!(checker.value(0.25, 0.25, &p), white);
+/// assert_eq!(checker.value(0.75, 0.25, &p), black);
+/// assert_eq!(checker.value(0.75, 0.75, &p), white);
+/// ```
+pub struct Checker {
+    pub even: ArcTexture,
+    pub odd: ArcTexture,
+    /// The number of squares along each of the `u` and `v` axes.
+    pub squares: Real,
+}
+
+impl Checker {
+    pub fn new(squares: Real, even: impl Into<ArcTexture>, odd: impl Into<ArcTexture>) -> Self {
+        Self {
+            even: even.into(),
+            odd: odd.into(),
+            squares,
+        }
+    }
+}
+
+impl Into<ArcTexture> for Checker {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Checker {
+    /// The texture of the square that `(u, v)` falls in.
+    fn square(&self, u: Real, v: Real) -> &ArcTexture {
+        let u_square = (u * self.squares).floor() as i64;
+        let v_square = (v * self.squares).floor() as i64;
+
+        if (u_square + v_square).rem_euclid(2) == 0 {
+            &self.even
+        } else {
+            &self.odd
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: Real, v: Real, p: &Point3) -> Colour {
+        self.square(u, v).value(u, v, p)
+    }
+
+    fn value_at(&self, hit_record: &HitRecord) -> Colour {
+        self.square(hit_record.u, hit_record.v).value_at(hit_record)
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::{
+    texture::{
+        noise::{domain_warp, fbm, Simplex},
+        ArcTexture, Texture,
+    },
+    Colour, Point3, Real,
+};
+
+/// A procedural cloud texture, made from domain warped fractal noise.
+pub struct Clouds {
+    noise: Simplex,
+    /// How many clouds there are per unit, roughly.
+    pub scale: Real,
+    /// How much of the sky is covered, between 0 and 1.
+    pub coverage: Real,
+    pub sky: Colour,
+    pub cloud: Colour,
+}
+
+impl Clouds {
+    /// Create white clouds on a blue sky.
+    pub fn new(seed: u64, scale: Real) -> Self {
+        Self {
+            noise: Simplex::new(seed),
+            scale,
+            coverage: 0.5,
+            sky: Colour::new(0.3, 0.5, 0.9),
+            cloud: Colour::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Into<ArcTexture> for Clouds {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Texture for Clouds {
+    fn value(&self, _: Real, _: Real, p: &Point3) -> Colour {
+        let p = domain_warp(&self.noise, &(*p * self.scale), 3, 0.8);
+        let density = 0.5 + 0.5 * fbm(&self.noise, &p, 6, 2.0, 0.5);
+
+        // Smoothly fade from sky to cloud around the coverage threshold.
+        let edge = 1.0 - self.coverage;
+        let t = ((density - edge + 0.1) / 0.2).clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+
+        self.sky * (1.0 - t) + self.cloud * t
+    }
+}
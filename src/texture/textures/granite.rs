@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::{
+    texture::{
+        noise::{fbm, Simplex, Worley},
+        ArcTexture, Texture,
+    },
+    Colour, Point3, Real,
+};
+
+/// A procedural granite texture, made from crystals that shade slowly between `light` and `dark`, with `dark` edges
+/// between them and small `speckle` coloured flecks over the top.
+pub struct Granite {
+    cells: Worley,
+    speckles: Simplex,
+    /// How many crystals there are per unit.
+    pub scale: Real,
+    pub light: Colour,
+    pub dark: Colour,
+    pub speckle: Colour,
+}
+
+impl Granite {
+    /// Create a pinkish grey granite texture.
+    pub fn new(seed: u64, scale: Real) -> Self {
+        Self {
+            cells: Worley::new(seed),
+            speckles: Simplex::new(seed.wrapping_add(1)),
+            scale,
+            light: Colour::new(0.75, 0.68, 0.66),
+            dark: Colour::new(0.35, 0.33, 0.33),
+            speckle: Colour::new(0.08, 0.08, 0.09),
+        }
+    }
+}
+
+impl Into<ArcTexture> for Granite {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Texture for Granite {
+    fn value(&self, _: Real, _: Real, p: &Point3) -> Colour {
+        let p = *p * self.scale;
+        let (f1, f2) = self.cells.distances(&p);
+
+        // The shade of the crystals drifts slowly across the surface.
+        let shade = 0.5 + 0.5 * fbm(&self.speckles, &(p * 0.5), 2, 2.0, 0.5);
+        let base = self.light * shade + self.dark * (1.0 - shade);
+
+        // Fade to the dark colour at the thin boundaries between crystals, where F1 and F2 are nearly equal.
+        let edge = ((f2 - f1) * 8.0).min(1.0);
+        let base = base * edge + self.dark * (1.0 - edge);
+
+        // Scatter small dark speckles over the surface.
+        let speckle = fbm(&self.speckles, &(p * 6.0), 3, 2.0, 0.5);
+        if speckle > 0.35 {
+            self.speckle
+        } else {
+            base
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use image::{DynamicImage, ImageError};
+
+use crate::{
+    hittable::HitRecord,
+    texture::{ArcTexture, Texture},
+    Colour, Point3, Real,
+};
+
+/// How texture coordinates outside of the image are handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressMode {
+    /// Repeat the image.
+    Wrap,
+    /// Stretch the pixels at the edge of the image.
+    Clamp,
+    /// Repeat the image, flipping every other copy so the edges line up.
+    Mirror,
+}
+
+impl AddressMode {
+    /// Map a pixel index onto an image with `size` pixels along that axis.
+    fn apply(&self, i: i64, size: usize) -> usize {
+        let size = size as i64;
+
+        let i = match self {
+            AddressMode::Wrap => i.rem_euclid(size),
+            AddressMode::Clamp => i.clamp(0, size - 1),
+            AddressMode::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                if i >= size {
+                    2 * size - 1 - i
+                } else {
+                    i
+                }
+            }
+        };
+
+        i as usize
+    }
+}
+
+/// How the pixels of an image are combined when it's sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Use the closest pixel.
+    Nearest,
+    /// Blend the four closest pixels.
+    Bilinear,
+    /// Blend the four closest pixels on the two mip levels closest to the size of the area a ray covers, which stops
+    /// detailed images from aliasing in the distance.
+    Trilinear,
+}
+
+/// One level of an image's mip chain, with its pixels converted to linear colour.
+struct MipLevel {
+    width: usize,
+    height: usize,
+    pixels: Vec<Colour>,
+}
+
+impl MipLevel {
+    fn pixel(&self, x: i64, y: i64, address_mode: AddressMode) -> Colour {
+        let x = address_mode.apply(x, self.width);
+        let y = address_mode.apply(y, self.height);
+
+        self.pixels[y * self.width + x]
+    }
+
+    /// Create the next level down by averaging each 2x2 block of pixels.
+    fn downsample(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut colour = Colour::default();
+
+                // Clamp to the edge so odd sized images don't read outside of the level.
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (2 * x + dx).min(self.width - 1);
+                    let sy = (2 * y + dy).min(self.height - 1);
+                    colour += self.pixels[sy * self.width + sx];
+                }
+
+                pixels.push(colour * 0.25);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn sample_nearest(&self, u: Real, v: Real, address_mode: AddressMode) -> Colour {
+        let x = (u * self.width as Real).floor() as i64;
+        let y = (v * self.height as Real).floor() as i64;
+
+        self.pixel(x, y, address_mode)
+    }
+
+    fn sample_bilinear(&self, u: Real, v: Real, address_mode: AddressMode) -> Colour {
+        // Pixel centres are at half-integer coordinates.
+        let x = u * self.width as Real - 0.5;
+        let y = v * self.height as Real - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.pixel(x0, y0, address_mode) * (1.0 - fx)
+            + self.pixel(x0 + 1, y0, address_mode) * fx;
+        let bottom = self.pixel(x0, y0 + 1, address_mode) * (1.0 - fx)
+            + self.pixel(x0 + 1, y0 + 1, address_mode) * fx;
+
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Convert an sRGB encoded value between 0 and 1 to linear.
+fn srgb_to_linear(value: Real) -> Real {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// A texture that maps an image onto a surface.
+///
+/// The image's mip levels are built once when the texture is created, and are shared between every clone of the
+/// texture, so it's cheap to use the same image on many objects and across threads.
+#[derive(Clone)]
+pub struct ImageTexture {
+    levels: Arc<[MipLevel]>,
+    pub address_mode: AddressMode,
+    pub filter: TextureFilter,
+}
+
+impl ImageTexture {
+    /// Load an image from a file. 8 and 16 bit images are assumed to be sRGB encoded and are converted to linear
+    /// colour, while floating point images are assumed to be linear already.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        Ok(Self::from_image(&image::open(path)?))
+    }
+
+    /// Load an image from a file without any colour conversion. This is what's wanted for images that hold data
+    /// rather than colours, like normal maps and glTF's metallic-roughness textures.
+    pub fn open_linear<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        Ok(Self::from_linear_image(&image::open(path)?))
+    }
+
+    /// Load the alpha channel of an image from a file as a grey texture, for use as the opacity of an
+    /// [AlphaMask](crate::material::materials::AlphaMask). Images without an alpha channel are completely opaque.
+    pub fn open_alpha<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        Ok(Self::from_alpha_image(&image::open(path)?))
+    }
+
+    /// Create a texture from an image that has already been loaded. See [ImageTexture::open].
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let is_linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+
+        Self::build(image, is_linear)
+    }
+
+    /// Create a texture from an image that has already been loaded, without any colour conversion. See
+    /// [ImageTexture::open_linear].
+    pub fn from_linear_image(image: &DynamicImage) -> Self {
+        Self::build(image, true)
+    }
+
+    /// Create a texture from the alpha channel of an image that has already been loaded. See
+    /// [ImageTexture::open_alpha].
+    /// ```
+    /// # use image::{DynamicImage, Rgba, RgbaImage};
+    /// # use magnetite::texture::textures::ImageTexture;
+    /// # use magnetite::Colour;
+    /// let image = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 51]));
+    ///
+    /// let texture = ImageTexture::from_alpha_image(&DynamicImage::ImageRgba8(image));
+    /// assert!((texture.sample(0.5, 0.5, 0.0) - Colour::new(0.2, 0.2, 0.2)).length() < 1e-6);
+    /// ```
+    pub fn from_alpha_image(image: &DynamicImage) -> Self {
+        let mut alpha = image.to_rgba32f();
+        for pixel in alpha.pixels_mut() {
+            let [_, _, _, a] = pixel.0;
+            pixel.0 = [a, a, a, 1.0];
+        }
+
+        Self::build(&DynamicImage::ImageRgba32F(alpha), true)
+    }
+
+    fn build(image: &DynamicImage, is_linear: bool) -> Self {
+        let rgb = image.to_rgb32f();
+        let decode = |value: f32| {
+            if is_linear {
+                value as Real
+            } else {
+                srgb_to_linear(value as Real)
+            }
+        };
+
+        let mut level = MipLevel {
+            width: rgb.width().max(1) as usize,
+            height: rgb.height().max(1) as usize,
+            pixels: rgb
+                .pixels()
+                .map(|p| Colour::new(decode(p.0[0]), decode(p.0[1]), decode(p.0[2])))
+                .collect(),
+        };
+
+        // An empty image is treated as a single black pixel.
+        if level.pixels.is_empty() {
+            level.pixels.push(Colour::default());
+        }
+
+        let mut levels = vec![];
+        while level.width > 1 || level.height > 1 {
+            let next = level.downsample();
+            levels.push(level);
+            level = next;
+        }
+        levels.push(level);
+
+        Self {
+            levels: levels.into(),
+            address_mode: AddressMode::Wrap,
+            filter: TextureFilter::Bilinear,
+        }
+    }
+
+    /// The width and height of the full size image in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.levels[0].width, self.levels[0].height)
+    }
+
+    /// The number of mip levels, including the full size image.
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Sample the texture at a given mip level, where `(0, 0)` is the bottom left of the image and level 0 is the full
+    /// size image. Fractional levels blend between the two closest mip levels with [TextureFilter::Trilinear], and are
+    /// rounded to the closest level otherwise.
+    /// ```
+    /// # use image::{DynamicImage, Rgb, RgbImage};
+    /// # use magnetite::texture::textures::{ImageTexture, TextureFilter};
+    /// # use magnetite::Colour;
+    /// // A 2x1 image that's black on the left and white on the right.
+    /// let mut image = RgbImage::new(2, 1);
+    /// image.put_pixel(1, 0, Rgb([255, 255, 255]));
+    ///
+    /// let mut texture = ImageTexture::from_image(&DynamicImage::ImageRgb8(image));
+    /// assert_eq!(texture.mip_levels(), 2);
+    ///
+    /// texture.filter = TextureFilter::Nearest;
+    /// assert_eq!(texture.sample(0.25, 0.5, 0.0), Colour::new(0.0, 0.0, 0.0));
+    /// assert_eq!(texture.sample(0.75, 0.5, 0.0), Colour::new(1.0, 1.0, 1.0));
+    ///
+    /// // The smallest mip level is the average of the whole image.
+    /// assert_eq!(texture.sample(0.25, 0.5, 1.0), Colour::new(0.5, 0.5, 0.5));
+    /// ```
+    pub fn sample(&self, u: Real, v: Real, level_of_detail: Real) -> Colour {
+        // Images are stored top to bottom, but `v` goes from bottom to top.
+        let v = 1.0 - v;
+
+        let max_level = (self.levels.len() - 1) as Real;
+        let level_of_detail = level_of_detail.clamp(0.0, max_level);
+
+        match self.filter {
+            TextureFilter::Nearest => {
+                let level = &self.levels[level_of_detail.round() as usize];
+                level.sample_nearest(u, v, self.address_mode)
+            }
+            TextureFilter::Bilinear => {
+                let level = &self.levels[level_of_detail.round() as usize];
+                level.sample_bilinear(u, v, self.address_mode)
+            }
+            TextureFilter::Trilinear => {
+                let lower = level_of_detail.floor();
+                let blend = level_of_detail - lower;
+                let lower = lower as usize;
+                let upper = (lower + 1).min(self.levels.len() - 1);
+
+                self.levels[lower].sample_bilinear(u, v, self.address_mode) * (1.0 - blend)
+                    + self.levels[upper].sample_bilinear(u, v, self.address_mode) * blend
+            }
+        }
+    }
+}
+
+impl Into<ArcTexture> for ImageTexture {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: Real, v: Real, _: &Point3) -> Colour {
+        self.sample(u, v, 0.0)
+    }
+
+    fn value_at(&self, hit_record: &HitRecord) -> Colour {
+        let (u, v) = (hit_record.u, hit_record.v);
+        if self.filter != TextureFilter::Trilinear {
+            return self.sample(u, v, 0.0);
+        }
+
+        // Pick the level where a pixel is as wide as the ray's footprint along whichever way it's widest. Each level
+        // halves the size of the one before it.
+        let (width, height) = self.size();
+        let (u_footprint, v_footprint) = hit_record.uv_footprint;
+        let pixels = (u_footprint * width as Real).max(v_footprint * height as Real);
+
+        self.sample(u, v, pixels.max(1.0).log2())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+    use crate::{
+        hittable::{hittables::Quad, Hittable, Interval},
+        material::{materials::Lambertian, ArcMaterial},
+        ray::Ray,
+        Vec3,
+    };
+
+    #[test]
+    fn wider_footprints_sample_blurrier_levels() {
+        // A 64x64 image of single pixel black and white checks, which averages to grey.
+        let image = RgbImage::from_fn(64, 64, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        let texture = ImageTexture {
+            filter: TextureFilter::Trilinear,
+            ..ImageTexture::from_image(&DynamicImage::ImageRgb8(image))
+        };
+
+        // A unit square facing the origin, with the ray hitting the middle of a pixel.
+        let grey: ArcMaterial = Lambertian::new(Colour::new(0.5, 0.5, 0.5)).into();
+        let quad = Quad::new(
+            Point3::new(-0.5, -0.5, -1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            &grey,
+        );
+        let centre = Point3::new(0.5 / 64.0, 0.5 / 64.0, -1.0);
+        let lookup = |spread: Real| {
+            let ray = Ray {
+                spread,
+                ..Ray::new(Point3::new(0.0, 0.0, 0.0), centre)
+            };
+            let hit = quad.hit(&ray, &Interval::new(0.0, Real::INFINITY)).unwrap();
+
+            texture.value_at(&hit)
+        };
+
+        // A ray without a footprint sees a single pixel.
+        let sharp = lookup(0.0);
+        assert!(sharp == Colour::new(0.0, 0.0, 0.0) || sharp == Colour::new(1.0, 1.0, 1.0));
+
+        // A footprint narrower than a pixel stays sharp, but one as wide as the whole square sees the average.
+        assert_eq!(lookup(0.5 / 64.0), sharp);
+        let blurry = lookup(1.0);
+        assert!((blurry - Colour::new(0.5, 0.5, 0.5)).length() < 1e-3);
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use num_traits::FloatConst;
+
+use crate::{
+    texture::{
+        noise::{turbulence, Perlin},
+        ArcTexture, Texture,
+    },
+    Colour, Point3, Real,
+};
+
+/// A procedural marble texture, made from thin veins that are distorted by turbulent noise.
+pub struct Marble {
+    noise: Perlin,
+    /// How many veins there are per unit along the `z` axis.
+    pub scale: Real,
+    /// How much the veins are distorted.
+    pub turbulence: Real,
+    pub light: Colour,
+    pub dark: Colour,
+}
+
+impl Marble {
+    /// Create a white marble texture with grey veins.
+    pub fn new(seed: u64, scale: Real) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            turbulence: 4.0,
+            light: Colour::new(0.9, 0.9, 0.88),
+            dark: Colour::new(0.25, 0.25, 0.28),
+        }
+    }
+}
+
+impl Into<ArcTexture> for Marble {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _: Real, _: Real, p: &Point3) -> Colour {
+        let distortion = self.turbulence * turbulence(&self.noise, &(*p * self.scale), 7);
+        let bands = (self.scale * p.z() * Real::PI() + distortion).sin().abs();
+
+        // The veins run along where the bands cross zero, and are sharpened so they stay thin.
+        let t = (1.0 - bands).powi(6);
+        self.light * (1.0 - t) + self.dark * t
+    }
+}
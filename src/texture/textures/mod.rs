@@ -0,0 +1,15 @@
+mod checker;
+mod clouds;
+mod granite;
+mod image_texture;
+mod marble;
+mod solid_colour;
+mod wood;
+
+pub use checker::Checker;
+pub use clouds::Clouds;
+pub use granite::Granite;
+pub use image_texture::{AddressMode, ImageTexture, TextureFilter};
+pub use marble::Marble;
+pub use solid_colour::SolidColour;
+pub use wood::Wood;
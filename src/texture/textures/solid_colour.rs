@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{
+    texture::{ArcTexture, Texture},
+    Colour, Point3, Real,
+};
+
+/// A texture that's the same colour everywhere.
+pub struct SolidColour {
+    pub colour: Colour,
+}
+
+impl SolidColour {
+    pub fn new(colour: Colour) -> Self {
+        Self { colour }
+    }
+}
+
+impl Into<ArcTexture> for SolidColour {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+// This lets a plain colour be used anywhere a texture is expected.
+impl Into<ArcTexture> for Colour {
+    fn into(self) -> ArcTexture {
+        Arc::new(SolidColour::new(self))
+    }
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _: Real, _: Real, _: &Point3) -> Colour {
+        self.colour
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::{
+    texture::{
+        noise::{fbm, Perlin},
+        ArcTexture, Texture,
+    },
+    Colour, Point3, Real,
+};
+
+/// A procedural wood texture, made from growth rings around the `y` axis that are wobbled by noise.
+pub struct Wood {
+    noise: Perlin,
+    /// The size of the noise that wobbles the rings, as a frequency.
+    pub scale: Real,
+    /// How many rings there are per unit of distance from the `y` axis.
+    pub ring_frequency: Real,
+    /// How much the rings are wobbled.
+    pub distortion: Real,
+    pub light: Colour,
+    pub dark: Colour,
+}
+
+impl Wood {
+    /// Create a light brown wood texture.
+    pub fn new(seed: u64, ring_frequency: Real) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale: 2.0,
+            ring_frequency,
+            distortion: 0.3,
+            light: Colour::new(0.72, 0.52, 0.3),
+            dark: Colour::new(0.42, 0.25, 0.12),
+        }
+    }
+}
+
+impl Into<ArcTexture> for Wood {
+    fn into(self) -> ArcTexture {
+        Arc::new(self)
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _: Real, _: Real, p: &Point3) -> Colour {
+        let wobble = self.distortion * fbm(&self.noise, &(*p * self.scale), 4, 2.0, 0.5);
+        let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+
+        let rings = (radius + wobble) * self.ring_frequency;
+        let ring = rings - rings.floor();
+
+        // Each ring fades from early wood to a thin band of dark late wood.
+        let t = ring.powi(4);
+        self.light * (1.0 - t) + self.dark * t
+    }
+}
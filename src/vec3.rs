@@ -22,25 +22,20 @@ impl<T> Vec3<T> {
 
 impl<T: SampleUniform + Float + DivAssign + FloatConst> Vec3<T> {
     pub fn random_in_unit_sphere() -> Self {
-        let mut rng = thread_rng();
-        let mut x = rng.gen_range(T::zero()..T::one());
-        let mut y = rng.gen_range(T::zero()..T::one());
-        let mut z = rng.gen_range(T::zero()..T::one());
-
-        let mag = (x * x + y * y + z * z).sqrt();
-
-        x /= mag;
-        y /= mag;
-        z /= mag;
-
-        let u = rng.gen_range(T::zero()..T::one());
-        let c = u.cbrt();
-
-        Self::new(x * c, y * c, z * c)
+        Self::random_unit_vector() * thread_rng().gen_range(T::zero()..T::one()).cbrt()
     }
 
+    /// Pick a direction uniformly over the whole sphere of directions.
     pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vector()
+        let mut rng = thread_rng();
+        let two = T::one() + T::one();
+
+        // Archimedes' hat-box theorem: picking the height uniformly gives a uniform point on the sphere.
+        let z = rng.gen_range(-T::one()..T::one());
+        let phi = rng.gen_range(T::zero()..T::one()) * two * T::PI();
+        let r = (T::one() - z * z).max(T::zero()).sqrt();
+
+        Self::new(r * phi.cos(), r * phi.sin(), z)
     }
 
     pub fn random_in_unit_disk() -> Self {
@@ -181,6 +176,25 @@ impl<T: Mul<Output = T> + Add<Output = T> + Copy + Float> Vec3<T> {
 
         r_out_perp + r_out_parallel
     }
+
+    /// Find two unit vectors that are perpendicular to this one and to each other. This vector must be a unit vector.
+    ///
+    /// The basis changes smoothly with the vector everywhere except when `z` is exactly zero, using the method from
+    /// "Building an Orthonormal Basis, Revisited" (Duff et al. 2017).
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let sign = T::one().copysign(self.2);
+        let a = -T::one() / (sign + self.2);
+        let b = self.0 * self.1 * a;
+
+        (
+            Self::new(
+                T::one() + sign * self.0 * self.0 * a,
+                sign * b,
+                -sign * self.0,
+            ),
+            Self::new(b, sign + self.1 * self.1 * a, -self.1),
+        )
+    }
 }
 
 impl<T: Sub<Output = T> + Mul<Output = T> + Copy> Vec3<T> {